@@ -1,115 +1,288 @@
 use std::collections::HashMap;
-use std::{fs::read_to_string, io};
+use std::{env, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let file = read_to_string("src/bin/day03-crossed-wires/input.txt")?;
     let input: Vec<Vec<&str>> = file
         .trim()
         .split('\n')
-        .map(|line| line.split(",").collect())
+        .map(|line| line.split(',').collect())
         .collect();
 
-    let tuple = walk_panel(input);
+    match env::args().nth(1).as_deref() {
+        Some("intersections") => {
+            let wires: Vec<Wire> = input.iter().map(|wire| Wire::parse(wire)).collect();
+            for intersection in find_intersections(&wires) {
+                println!(
+                    "{:?} wires={:?} distance={} steps={:?}",
+                    intersection.coordinates,
+                    intersection.wires(),
+                    intersection.distance(),
+                    intersection.steps
+                );
+            }
+            for overlap in find_overlaps(&wires) {
+                println!(
+                    "overlap wires={:?} from={:?} to={:?}",
+                    overlap.wires, overlap.from, overlap.to
+                );
+            }
+            for (index, wire) in wires.iter().enumerate() {
+                for point in find_self_crossings(wire) {
+                    println!("self-crossing wire={} {:?}", index, point);
+                }
+            }
+        }
+        Some("export") => {
+            let wires: Vec<Wire> = input.iter().map(|wire| Wire::parse(wire)).collect();
+            println!("{}", export_paths(&wires));
+        }
+        _ => {
+            let tuple = walk_panel(input);
 
-    println!("{}", tuple.0);
-    println!("{}", tuple.1);
+            println!("{}", tuple.0);
+            println!("{}", tuple.1);
+        }
+    }
 
     Ok(())
 }
 
-fn get_coordinates(coordinates: &Vec<i32>) -> String {
-    coordinates[0].to_string() + "," + &coordinates[1].to_string()
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    start: (i32, i32),
+    end: (i32, i32),
+    steps: i32,
 }
 
-fn walk_panel(wires: Vec<Vec<&str>>) -> (i32, i32) {
-    let mut panel: HashMap<String, i32> = HashMap::new();
-    let mut steps: Vec<HashMap<String, i32>> = vec![];
-    let mut wire_index: i32 = 0;
-    let mut step_index: usize = 0;
-
-    for wire in wires.iter() {
-        wire_index += 1;
-        let mut coords: Vec<i32> = vec![0, 0];
-        let mut step: i32 = 0;
-        steps.push(HashMap::new());
-
-        for movement in wire.iter() {
-            let direction = &movement[0..1];
-            let times = &movement[1..].parse::<i32>().unwrap() + 1;
-            let coord: usize;
-            let walk: i32;
-            match direction {
-                "R" => {
-                    coord = 0;
-                    walk = 1;
-                }
-                "L" => {
-                    coord = 0;
-                    walk = -1;
-                }
-                "U" => {
-                    coord = 1;
-                    walk = 1;
-                }
-                "D" => {
-                    coord = 1;
-                    walk = -1;
-                }
+impl Segment {
+    fn x_range(&self) -> (i32, i32) {
+        (self.start.0.min(self.end.0), self.start.0.max(self.end.0))
+    }
+
+    fn y_range(&self) -> (i32, i32) {
+        (self.start.1.min(self.end.1), self.start.1.max(self.end.1))
+    }
+
+    fn steps_to(&self, coordinates: &(i32, i32)) -> i32 {
+        self.steps + manhattan_distance(&self.start, coordinates)
+    }
+
+    fn crossing(&self, other: &Segment) -> Option<((i32, i32), (i32, i32))> {
+        let (min_x, max_x) = self.x_range();
+        let (other_min_x, other_max_x) = other.x_range();
+        let (min_y, max_y) = self.y_range();
+        let (other_min_y, other_max_y) = other.y_range();
+
+        let from = (min_x.max(other_min_x), min_y.max(other_min_y));
+        let to = (max_x.min(other_max_x), max_y.min(other_max_y));
+        if from.0 > to.0 || from.1 > to.1 {
+            return None;
+        }
+        Some((from, to))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Wire {
+    segments: Vec<Segment>,
+}
+
+impl Wire {
+    fn parse(movements: &[&str]) -> Self {
+        let mut segments = vec![];
+        let mut start = (0, 0);
+        let mut steps = 0;
+
+        for movement in movements.iter() {
+            let length = movement[1..].parse::<i32>().unwrap();
+            let end = match &movement[0..1] {
+                "R" => (start.0 + length, start.1),
+                "L" => (start.0 - length, start.1),
+                "U" => (start.0, start.1 + length),
+                "D" => (start.0, start.1 - length),
                 n => panic!("unknown direction: {}", n),
+            };
+            segments.push(Segment { start, end, steps });
+            start = end;
+            steps += length;
+        }
+
+        Wire { segments }
+    }
+
+    fn vertices(&self) -> Vec<(i32, i32)> {
+        let mut vertices = vec![(0, 0)];
+        vertices.extend(self.segments.iter().map(|segment| segment.end));
+        vertices
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Intersection {
+    coordinates: (i32, i32),
+    steps: Vec<(usize, i32)>,
+}
+
+impl Intersection {
+    fn distance(&self) -> i32 {
+        manhattan_distance(&(0, 0), &self.coordinates)
+    }
+
+    fn wires(&self) -> Vec<usize> {
+        self.steps.iter().map(|&(wire, _)| wire).collect()
+    }
+
+    fn steps_sum(&self) -> i32 {
+        self.steps.iter().map(|&(_, steps)| steps).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Overlap {
+    wires: (usize, usize),
+    from: (i32, i32),
+    to: (i32, i32),
+}
+
+fn manhattan_distance(from: &(i32, i32), to: &(i32, i32)) -> i32 {
+    (from.0 - to.0).abs() + (from.1 - to.1).abs()
+}
+
+fn crossing_points(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut points = vec![];
+    for x in from.0..=to.0 {
+        for y in from.1..=to.1 {
+            if (x, y) != (0, 0) {
+                points.push((x, y));
             }
+        }
+    }
+    points
+}
+
+fn find_intersections(wires: &[Wire]) -> Vec<Intersection> {
+    let mut crossings: HashMap<(i32, i32), HashMap<usize, i32>> = HashMap::new();
 
-            for _ in 1..times {
-                coords[coord] += walk;
-                step += 1;
-                let times_was_here: i32;
-                match panel.get(&get_coordinates(&coords)) {
-                    Some(&number) => {
-                        if number != wire_index {
-                            times_was_here = wire_index + number;
-                        } else {
-                            times_was_here = wire_index;
+    for (index, wire) in wires.iter().enumerate() {
+        for (other_index, other_wire) in wires.iter().enumerate().skip(index + 1) {
+            for segment in wire.segments.iter() {
+                for other_segment in other_wire.segments.iter() {
+                    if let Some((from, to)) = segment.crossing(other_segment) {
+                        for point in crossing_points(from, to) {
+                            let steps = crossings.entry(point).or_default();
+                            for (wire_index, segment) in
+                                [(index, segment), (other_index, other_segment)].iter()
+                            {
+                                let walked = segment.steps_to(&point);
+                                let entry = steps.entry(*wire_index).or_insert(walked);
+                                *entry = (*entry).min(walked);
+                            }
                         }
                     }
-                    _ => times_was_here = wire_index,
                 }
-                panel.insert(get_coordinates(&coords), times_was_here);
-                steps[step_index].insert(get_coordinates(&coords), step);
             }
         }
-        step_index += 1;
     }
 
-    let mut min_manhattan_distance = i32::max_value();
-    let mut min_steps_sum = i32::max_value();
+    let mut intersections: Vec<Intersection> = crossings
+        .into_iter()
+        .map(|(coordinates, steps)| {
+            let mut steps: Vec<(usize, i32)> = steps.into_iter().collect();
+            steps.sort_unstable();
+            Intersection { coordinates, steps }
+        })
+        .collect();
+    intersections.sort_by_key(|intersection| (intersection.distance(), intersection.coordinates));
+    intersections
+}
 
-    for (coords, times_crossed) in panel.iter() {
-        if times_crossed > &wire_index {
-            let manhattan_distance = coords
-                .split(",")
-                .map(|c| c.parse::<i32>().unwrap().abs())
-                .sum();
-            let steps_sum: i32 = steps
-                .iter()
-                .map(|hashmap| match hashmap.get(coords) {
-                    Some(&number) => number,
-                    _ => panic!("No step for crossing: {}", coords),
-                })
-                .sum();
-            if manhattan_distance < min_manhattan_distance {
-                min_manhattan_distance = manhattan_distance;
+fn find_self_crossings(wire: &Wire) -> Vec<(i32, i32)> {
+    let mut crossings = vec![];
+
+    for (index, segment) in wire.segments.iter().enumerate() {
+        for (other_index, other_segment) in wire.segments.iter().enumerate().skip(index + 1) {
+            if let Some((from, to)) = segment.crossing(other_segment) {
+                for point in crossing_points(from, to) {
+                    let is_joint = other_index == index + 1 && point == segment.end;
+                    if !is_joint && !crossings.contains(&point) {
+                        crossings.push(point);
+                    }
+                }
             }
+        }
+    }
+
+    crossings.sort_by_key(|point| (manhattan_distance(&(0, 0), point), *point));
+    crossings
+}
+
+fn find_overlaps(wires: &[Wire]) -> Vec<Overlap> {
+    let mut overlaps = vec![];
 
-            if steps_sum < min_steps_sum {
-                min_steps_sum = steps_sum;
+    for (index, wire) in wires.iter().enumerate() {
+        for (other_index, other_wire) in wires.iter().enumerate().skip(index + 1) {
+            for segment in wire.segments.iter() {
+                for other_segment in other_wire.segments.iter() {
+                    if let Some((from, to)) = segment.crossing(other_segment) {
+                        if from != to {
+                            overlaps.push(Overlap {
+                                wires: (index, other_index),
+                                from,
+                                to,
+                            });
+                        }
+                    }
+                }
             }
         }
     }
+
+    overlaps
+}
+
+fn export_paths(wires: &[Wire]) -> String {
+    wires
+        .iter()
+        .map(|wire| {
+            wire.vertices()
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn walk_panel(wires: Vec<Vec<&str>>) -> (i32, i32) {
+    let wires: Vec<Wire> = wires.iter().map(|wire| Wire::parse(wire)).collect();
+    let intersections = find_intersections(&wires);
+
+    let min_manhattan_distance = intersections
+        .iter()
+        .map(|intersection| intersection.distance())
+        .min()
+        .unwrap_or(i32::MAX);
+    let min_steps_sum = intersections
+        .iter()
+        .map(|intersection| intersection.steps_sum())
+        .min()
+        .unwrap_or(i32::MAX);
+
     (min_manhattan_distance, min_steps_sum)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::export_paths;
+    use super::find_intersections;
+    use super::find_overlaps;
+    use super::find_self_crossings;
     use super::walk_panel;
+    use super::Intersection;
+    use super::Overlap;
+    use super::Wire;
 
     #[test]
     fn it_should_find_lowest_manhattan_distance_1() {
@@ -168,4 +341,80 @@ mod tests {
         ];
         assert_eq!(walk_panel(wires).1, 410);
     }
+
+    #[test]
+    fn it_should_list_every_intersection() {
+        let wires = vec![
+            Wire::parse(&["R8", "U5", "L5", "D3"]),
+            Wire::parse(&["U7", "R6", "D4", "L4"]),
+        ];
+        assert_eq!(
+            find_intersections(&wires),
+            vec![
+                Intersection {
+                    coordinates: (3, 3),
+                    steps: vec![(0, 20), (1, 20)],
+                },
+                Intersection {
+                    coordinates: (6, 5),
+                    steps: vec![(0, 15), (1, 15)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_intersect_more_than_two_wires() {
+        let wires = vec![
+            Wire::parse(&["R8", "U5", "L5", "D3"]),
+            Wire::parse(&["U7", "R6", "D4", "L4"]),
+            Wire::parse(&["U3", "R10"]),
+        ];
+        let intersections = find_intersections(&wires);
+        let crossing = intersections
+            .iter()
+            .find(|intersection| intersection.coordinates == (3, 3))
+            .unwrap();
+
+        assert_eq!(intersections.len(), 10);
+        assert_eq!(crossing.wires(), vec![0, 1, 2]);
+        assert_eq!(crossing.steps, vec![(0, 20), (1, 20), (2, 6)]);
+        assert_eq!(crossing.distance(), 6);
+    }
+
+    #[test]
+    fn it_should_find_self_crossings() {
+        let wire = Wire::parse(&["R4", "U2", "L2", "D4", "R1", "L3"]);
+        assert_eq!(find_self_crossings(&wire), vec![(2, 0), (2, -2)]);
+    }
+
+    #[test]
+    fn it_should_find_overlapping_segments() {
+        let wires = vec![
+            Wire::parse(&["R8", "U2"]),
+            Wire::parse(&["U1", "R3", "D1", "R3"]),
+        ];
+
+        assert_eq!(
+            find_overlaps(&wires),
+            vec![Overlap {
+                wires: (0, 1),
+                from: (3, 0),
+                to: (6, 0),
+            }]
+        );
+        assert_eq!(
+            find_intersections(&wires)
+                .iter()
+                .map(|intersection| intersection.coordinates)
+                .collect::<Vec<(i32, i32)>>(),
+            vec![(3, 0), (4, 0), (5, 0), (6, 0)]
+        );
+    }
+
+    #[test]
+    fn it_should_export_paths() {
+        let wires = vec![Wire::parse(&["R8", "U5"]), Wire::parse(&["U7", "L6"])];
+        assert_eq!(export_paths(&wires), "0,0 8,0 8,5\n0,0 0,7 -6,7");
+    }
 }
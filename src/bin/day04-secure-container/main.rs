@@ -1,81 +1,484 @@
 use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::str::FromStr;
 
-fn main() {
-    let mut valid_codes = 0;
-    for i in 123257..647015 {
-        if is_valid(&i.to_string(), false) {
-            valid_codes += 1;
+fn main() -> io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("check") => {
+            let code = args
+                .get(1)
+                .ok_or_else(|| invalid_input("usage: check <code> [rules...]".to_owned()))?;
+            let rules = parse_rules(RuleSet::new(code.len()), &args[2..])?;
+            println!("{}", rules.is_valid(code));
+        }
+        Some(command @ "count") | Some(command @ "list") => {
+            if args.len() < 4 {
+                return Err(invalid_input(format!(
+                    "usage: {} <length> <low> <high> [rules...]",
+                    command
+                )));
+            }
+            let length = parse_number::<usize>(&args[1])?;
+            let low = parse_number::<u128>(&args[2])?;
+            let high = parse_number::<u128>(&args[3])?;
+            let rules = parse_rules(RuleSet::new(length), &args[4..])?;
+
+            if command == "count" {
+                println!("{}", rules.count(low, high).map_err(invalid_input)?);
+            } else {
+                for code in rules.list(low, high).map_err(invalid_input)? {
+                    println!("{:0width$}", code, width = length);
+                }
+            }
+        }
+        _ => {
+            println!("{}", puzzle_rules(false).count(123257, 647015).unwrap());
+            println!("{}", puzzle_rules(true).count(123257, 647015).unwrap());
         }
     }
-    println!("{}", valid_codes);
 
-    valid_codes = 0;
-    for i in 123257..647015 {
-        if is_valid(&i.to_string(), true) {
-            valid_codes += 1;
+    Ok(())
+}
+
+fn invalid_input(error: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+fn parse_number<T: FromStr>(number: &str) -> io::Result<T> {
+    number
+        .parse::<T>()
+        .map_err(|_| invalid_input(format!("Invalid number {}", number)))
+}
+
+fn parse_rules(rules: RuleSet, args: &[String]) -> io::Result<RuleSet> {
+    args.iter().try_fold(rules, |rules, rule| {
+        Ok(rules.with(rule.parse::<Rule>().map_err(invalid_input)?))
+    })
+}
+
+fn puzzle_rules(strict: bool) -> RuleSet {
+    let rules = RuleSet::new(6).with(Rule::NonDecreasing);
+    if strict {
+        rules.with(Rule::RunExactly(2))
+    } else {
+        rules.with(Rule::RunAtLeast(2))
+    }
+}
+
+const MAX_LENGTH: usize = 38;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rule {
+    NonDecreasing,
+    NonIncreasing,
+    RunAtLeast(usize),
+    RunExactly(usize),
+    RunAtMost(usize),
+    DigitCount { digit: u8, min: usize, max: usize },
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.parse::<usize>()
+                .map_err(|_| format!("Invalid rule {}", rule))
+        };
+
+        if rule == "non-decreasing" {
+            Ok(Rule::NonDecreasing)
+        } else if rule == "non-increasing" {
+            Ok(Rule::NonIncreasing)
+        } else if let Some(n) = rule.strip_prefix("run>=") {
+            Ok(Rule::RunAtLeast(parse(n)?))
+        } else if let Some(n) = rule.strip_prefix("run==") {
+            Ok(Rule::RunExactly(parse(n)?))
+        } else if let Some(n) = rule.strip_prefix("run<=") {
+            Ok(Rule::RunAtMost(parse(n)?))
+        } else if let Some(bounds) = rule.strip_prefix("digit") {
+            let mut parts = bounds.splitn(2, '=');
+            let digit = parse(parts.next().unwrap_or(""))?;
+            let mut range = parts.next().unwrap_or("").splitn(2, "..");
+            let min = parse(range.next().unwrap_or(""))?;
+            let max = parse(range.next().unwrap_or(""))?;
+            if digit > 9 {
+                return Err(format!("Invalid rule {}", rule));
+            }
+            Ok(Rule::DigitCount {
+                digit: digit as u8,
+                min,
+                max,
+            })
+        } else {
+            Err(format!("Invalid rule {}", rule))
         }
     }
-    println!("{}", valid_codes);
 }
 
-fn is_valid(code: &str, strict: bool) -> bool {
-    let mut has_double: bool = false;
-    let mut chars = code.chars();
-    let mut chars_map: HashMap<Option<u32>, i8> = HashMap::new();
-    let mut val = match chars.next() {
-        Some(x) => x,
-        None => panic!("must pass a string !"),
-    };
-    chars_map.insert(val.to_digit(10), 1);
-
-    loop {
-        match chars.next() {
-            Some(x) => {
-                let prev = val.to_digit(10);
-                let actual = x.to_digit(10);
-                if prev > actual {
-                    return false;
-                }
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Progress {
+    last: Option<u8>,
+    run: usize,
+    satisfied: Vec<bool>,
+    counts: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RuleSet {
+    length: usize,
+    rules: Vec<Rule>,
+}
 
-                if prev == actual {
-                    has_double = true;
+impl RuleSet {
+    fn new(length: usize) -> Self {
+        RuleSet {
+            length,
+            rules: vec![],
+        }
+    }
+
+    fn with(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    fn run_cap(&self) -> usize {
+        self.rules
+            .iter()
+            .map(|rule| match rule {
+                Rule::RunAtLeast(n) | Rule::RunExactly(n) | Rule::RunAtMost(n) => n + 1,
+                _ => 1,
+            })
+            .max()
+            .unwrap_or(1)
+    }
+
+    fn start(&self) -> Progress {
+        Progress {
+            last: None,
+            run: 0,
+            satisfied: self
+                .rules
+                .iter()
+                .map(|rule| !matches!(rule, Rule::RunAtLeast(_) | Rule::RunExactly(_)))
+                .collect(),
+            counts: vec![0; self.rules.len()],
+        }
+    }
+
+    fn push(&self, progress: &Progress, digit: u8) -> Option<Progress> {
+        let mut next = progress.clone();
+
+        if progress.last == Some(digit) {
+            next.run = (progress.run + 1).min(self.run_cap());
+        } else {
+            self.close_run(&mut next);
+            next.run = 1;
+        }
+        next.last = Some(digit);
+
+        for (ind, rule) in self.rules.iter().enumerate() {
+            match *rule {
+                Rule::NonDecreasing => {
+                    if progress.last.is_some_and(|last| digit < last) {
+                        return None;
+                    }
+                }
+                Rule::NonIncreasing => {
+                    if progress.last.is_some_and(|last| digit > last) {
+                        return None;
+                    }
                 }
+                Rule::RunAtLeast(n) => {
+                    if next.run >= n {
+                        next.satisfied[ind] = true;
+                    }
+                }
+                Rule::RunExactly(_) => (),
+                Rule::RunAtMost(n) => {
+                    if next.run > n {
+                        return None;
+                    }
+                }
+                Rule::DigitCount { digit: d, max, .. } => {
+                    if digit == d {
+                        next.counts[ind] += 1;
+                        if next.counts[ind] > max {
+                            return None;
+                        }
+                    }
+                }
+            }
+        }
+
+        Some(next)
+    }
 
-                let times: i8;
-                match chars_map.get(&actual) {
-                    Some(&number) => times = number + 1,
-                    _ => times = 1,
+    fn close_run(&self, progress: &mut Progress) {
+        for (ind, rule) in self.rules.iter().enumerate() {
+            if let Rule::RunExactly(n) = *rule {
+                if progress.run == n {
+                    progress.satisfied[ind] = true;
                 }
-                chars_map.insert(actual, times);
+            }
+        }
+    }
+
+    fn finish(&self, progress: &Progress) -> bool {
+        let mut last = progress.clone();
+        self.close_run(&mut last);
+
+        self.rules
+            .iter()
+            .enumerate()
+            .all(|(ind, rule)| match *rule {
+                Rule::DigitCount { min, .. } => last.counts[ind] >= min,
+                _ => last.satisfied[ind],
+            })
+    }
 
-                val = x;
+    fn is_valid(&self, code: &str) -> bool {
+        if code.len() != self.length {
+            return false;
+        }
+
+        let mut progress = self.start();
+        for c in code.chars() {
+            let digit = match c.to_digit(10) {
+                Some(digit) => digit as u8,
+                None => return false,
+            };
+            progress = match self.push(&progress, digit) {
+                Some(next) => next,
+                None => return false,
+            };
+        }
+        self.finish(&progress)
+    }
+
+    fn check_length(&self) -> Result<(), String> {
+        if self.length > MAX_LENGTH {
+            return Err(format!(
+                "Codes longer than {} digits are not supported",
+                MAX_LENGTH
+            ));
+        }
+        Ok(())
+    }
+
+    fn digits(&self, number: u128) -> Vec<u8> {
+        let mut digits = vec![0; self.length];
+        let mut rest = number;
+        for digit in digits.iter_mut().rev() {
+            *digit = (rest % 10) as u8;
+            rest /= 10;
+        }
+        digits
+    }
+
+    fn largest(&self) -> u128 {
+        10u128.pow(self.length as u32) - 1
+    }
+
+    fn count(&self, low: u128, high: u128) -> Result<u128, String> {
+        self.check_length()?;
+        if low > high || low > self.largest() {
+            return Ok(0);
+        }
+
+        let up_to_high = self.count_up_to(high.min(self.largest()));
+        if low == 0 {
+            return Ok(up_to_high);
+        }
+        Ok(up_to_high - self.count_up_to(low - 1))
+    }
+
+    fn count_up_to(&self, bound: u128) -> u128 {
+        let mut memo: HashMap<(usize, Progress), u128> = HashMap::new();
+        self.count_from(&self.digits(bound), 0, true, self.start(), &mut memo)
+    }
+
+    fn count_from(
+        &self,
+        bound: &[u8],
+        position: usize,
+        tight: bool,
+        progress: Progress,
+        memo: &mut HashMap<(usize, Progress), u128>,
+    ) -> u128 {
+        if position == self.length {
+            return if self.finish(&progress) { 1 } else { 0 };
+        }
+
+        if !tight {
+            if let Some(&count) = memo.get(&(position, progress.clone())) {
+                return count;
+            }
+        }
+
+        let limit = if tight { bound[position] } else { 9 };
+        let mut count = 0;
+        for digit in 0..=limit {
+            if let Some(next) = self.push(&progress, digit) {
+                count += self.count_from(bound, position + 1, tight && digit == limit, next, memo);
             }
-            None => break,
         }
+
+        if !tight {
+            memo.insert((position, progress), count);
+        }
+        count
     }
 
-    if !strict {
-        return has_double;
+    fn list(&self, low: u128, high: u128) -> Result<Vec<u128>, String> {
+        self.check_length()?;
+        let mut codes = vec![];
+        if low <= high && low <= self.largest() {
+            let high = self.digits(high.min(self.largest()));
+            self.list_from(
+                &self.digits(low),
+                &high,
+                0,
+                true,
+                true,
+                0,
+                self.start(),
+                &mut codes,
+            );
+        }
+        Ok(codes)
     }
 
-    chars_map.values().any(|v| v == &2)
+    #[allow(clippy::too_many_arguments)]
+    fn list_from(
+        &self,
+        low: &[u8],
+        high: &[u8],
+        position: usize,
+        low_tight: bool,
+        high_tight: bool,
+        prefix: u128,
+        progress: Progress,
+        codes: &mut Vec<u128>,
+    ) {
+        if position == self.length {
+            if self.finish(&progress) {
+                codes.push(prefix);
+            }
+            return;
+        }
+
+        let from = if low_tight { low[position] } else { 0 };
+        let to = if high_tight { high[position] } else { 9 };
+        for digit in from..=to {
+            if let Some(next) = self.push(&progress, digit) {
+                self.list_from(
+                    low,
+                    high,
+                    position + 1,
+                    low_tight && digit == from,
+                    high_tight && digit == to,
+                    prefix * 10 + digit as u128,
+                    next,
+                    codes,
+                );
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::is_valid;
+    use super::puzzle_rules;
+    use super::Rule;
+    use super::RuleSet;
 
     #[test]
     fn it_should_know_if_a_code_is_valid() {
-        assert_eq!(is_valid(&"111111", false), true);
-        assert_eq!(is_valid(&"223450", false), false);
-        assert_eq!(is_valid(&"123789", false), false);
+        assert!(puzzle_rules(false).is_valid("111111"));
+        assert!(!puzzle_rules(false).is_valid("223450"));
+        assert!(!puzzle_rules(false).is_valid("123789"));
     }
 
     #[test]
     fn it_should_know_if_a_code_is_strictly_valid() {
-        assert_eq!(is_valid(&"112233", true), true);
-        assert_eq!(is_valid(&"123444", true), false);
-        assert_eq!(is_valid(&"111122", true), true);
+        assert!(puzzle_rules(true).is_valid("112233"));
+        assert!(!puzzle_rules(true).is_valid("123444"));
+        assert!(puzzle_rules(true).is_valid("111122"));
+    }
+
+    #[test]
+    fn it_should_count_codes_like_brute_force() {
+        let rules = RuleSet::new(6)
+            .with(Rule::NonDecreasing)
+            .with(Rule::RunExactly(2));
+        let brute_force = (123257..=647015)
+            .filter(|code: &u128| rules.is_valid(&code.to_string()))
+            .count() as u128;
+
+        assert_eq!(rules.count(123257, 647015), Ok(brute_force));
+        assert_eq!(
+            rules.list(123257, 647015).unwrap().len() as u128,
+            brute_force
+        );
+    }
+
+    #[test]
+    fn it_should_combine_rules() {
+        let rules = RuleSet::new(4)
+            .with(Rule::NonIncreasing)
+            .with(Rule::RunAtMost(2))
+            .with(Rule::DigitCount {
+                digit: 7,
+                min: 1,
+                max: 1,
+            });
+
+        assert!(!rules.is_valid("9771"));
+        assert!(rules.is_valid("9973"));
+        assert!(!rules.is_valid("7666"));
+        assert_eq!(rules.list(9700, 9712), Ok(vec![9700, 9710, 9711]));
+        assert_eq!(rules.count(9700, 9730), Ok(7));
+    }
+
+    #[test]
+    fn it_should_parse_rules() {
+        assert_eq!("non-decreasing".parse::<Rule>(), Ok(Rule::NonDecreasing));
+        assert_eq!("run==2".parse::<Rule>(), Ok(Rule::RunExactly(2)));
+        assert_eq!("run<=3".parse::<Rule>(), Ok(Rule::RunAtMost(3)));
+        assert_eq!(
+            "digit7=1..2".parse::<Rule>(),
+            Ok(Rule::DigitCount {
+                digit: 7,
+                min: 1,
+                max: 2
+            })
+        );
+        assert!("run=>2".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn it_should_count_long_codes() {
+        let rules = RuleSet::new(18).with(Rule::NonDecreasing);
+
+        assert_eq!(rules.count(0, u128::MAX), Ok(4686825));
+        assert_eq!(rules.list(0, 10).unwrap().len(), 10);
+
+        let rules = RuleSet::new(20).with(Rule::NonDecreasing);
+
+        assert_eq!(rules.count(0, u128::MAX), Ok(10015005));
+        assert_eq!(rules.count(0, u64::MAX as u128), Ok(9126955));
+    }
+
+    #[test]
+    fn it_should_reject_codes_too_long_to_count() {
+        let rules = RuleSet::new(39).with(Rule::NonDecreasing);
+
+        assert!(rules.count(0, 1).is_err());
+        assert!(rules.list(0, 1).is_err());
     }
 }
@@ -1,93 +1,304 @@
-use std::collections::HashMap;
-use std::{fs::read_to_string, io};
+use std::collections::{HashMap, VecDeque};
+use std::{env, fmt, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let input = read_to_string("src/bin/day06-universal-orbit-map/input.txt")?;
     let planets: Vec<&str> = input.trim().split('\n').collect();
+    let tree = OrbitTree::parse(&planets).map_err(invalid_data)?;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arg = |ind: usize| args.get(ind).map(|arg| arg.as_str()).unwrap_or("");
 
-    println!("{}", get_total_of_orbits(&planets));
-    println!("{}", get_shorter_path(&planets));
+    match arg(0) {
+        "depth" => println!("{}", tree.depth(arg(1)).map_err(invalid_data)?),
+        "lca" => println!(
+            "{}",
+            tree.lowest_common_ancestor(arg(1), arg(2))
+                .map_err(invalid_data)?
+        ),
+        "transfers" => println!("{}", tree.transfers(arg(1), arg(2)).map_err(invalid_data)?),
+        "dot" => {
+            let highlight = match (args.get(1), args.get(2)) {
+                (Some(from), Some(to)) => Some((from.as_str(), to.as_str())),
+                _ => None,
+            };
+            print!("{}", tree.to_dot(highlight).map_err(invalid_data)?);
+        }
+        "tree" => print!("{}", tree.to_text()),
+        "subtree" => {
+            for body in tree.subtree(arg(1)).map_err(invalid_data)? {
+                println!("{}", body);
+            }
+        }
+        _ => {
+            println!("{}", tree.total_orbits());
+            println!("{}", tree.transfers("YOU", "SAN").map_err(invalid_data)?);
+        }
+    }
     Ok(())
 }
 
-fn walk_orbits(universe: &HashMap<&str, &str>, satellite: &str) -> i32 {
-    match universe.get(satellite) {
-        Some(center) => 1 + walk_orbits(&universe, &center),
-        _ => 0,
-    }
+fn invalid_data(error: OrbitError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
 }
 
-fn get_total_of_orbits(planets: &Vec<&str>) -> i32 {
-    let mut universe = HashMap::new();
+#[derive(Debug, Clone, PartialEq)]
+enum OrbitError {
+    InvalidOrbit(String),
+    MultipleCenters(String),
+    MultipleRoots(Vec<String>),
+    Cycle(Vec<String>),
+    UnknownBody(String),
+    NoCenter(String),
+}
 
-    for str_orbit in planets {
-        let orbit: Vec<&str> = str_orbit.split(')').collect();
-        let center = orbit[0];
-        let satellite = orbit[1];
-        universe.insert(satellite, center);
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OrbitError::InvalidOrbit(orbit) => write!(f, "Invalid orbit {}", orbit),
+            OrbitError::MultipleCenters(body) => write!(f, "{} orbits more than one center", body),
+            OrbitError::MultipleRoots(roots) => write!(f, "Multiple roots {}", roots.join(", ")),
+            OrbitError::Cycle(bodies) => write!(f, "Orbit cycle {}", bodies.join(")")),
+            OrbitError::UnknownBody(body) => write!(f, "Unknown body {}", body),
+            OrbitError::NoCenter(body) => write!(f, "{} does not orbit anything", body),
+        }
     }
+}
 
-    let mut n = 0;
-    for key in universe.keys() {
-        n += walk_orbits(&universe, &key);
-    }
-    n
+#[derive(Debug, Clone)]
+struct OrbitTree {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+    centers: Vec<Option<usize>>,
+    satellites: Vec<Vec<usize>>,
+    depths: Vec<usize>,
+    root: usize,
 }
 
-fn get_shorter_path(planets: &Vec<&str>) -> usize {
-    let mut universe = HashMap::new();
-
-    for str_orbit in planets {
-        let orbit: Vec<&str> = str_orbit.split(')').collect();
-        let center = orbit[0];
-        let satellite = orbit[1];
-        universe.insert(satellite, center);
-    }
-
-    let mut planet_paths = Vec::new();
-    for planet in vec!["YOU", "SAN"] {
-        let mut path = Vec::new();
-        let mut satellite = planet;
-        loop {
-            match universe.get(satellite) {
-                Some(center) => {
-                    path.push(center);
-                    satellite = center;
+impl OrbitTree {
+    fn parse(planets: &[&str]) -> Result<Self, OrbitError> {
+        let mut names: Vec<String> = vec![];
+        let mut indices: HashMap<String, usize> = HashMap::new();
+        let mut centers: Vec<Option<usize>> = vec![];
+        let mut index_of =
+            |name: &str, names: &mut Vec<String>, centers: &mut Vec<Option<usize>>| {
+                *indices.entry(name.to_string()).or_insert_with(|| {
+                    names.push(name.to_string());
+                    centers.push(None);
+                    names.len() - 1
+                })
+            };
+
+        for str_orbit in planets {
+            let orbit: Vec<&str> = str_orbit.trim().split(')').collect();
+            if orbit.len() != 2 || orbit[0].is_empty() || orbit[1].is_empty() {
+                return Err(OrbitError::InvalidOrbit(str_orbit.to_string()));
+            }
+            let center = index_of(orbit[0], &mut names, &mut centers);
+            let satellite = index_of(orbit[1], &mut names, &mut centers);
+            match centers[satellite] {
+                Some(known) if known != center => {
+                    return Err(OrbitError::MultipleCenters(orbit[1].to_string()))
                 }
-                _ => break,
+                _ => centers[satellite] = Some(center),
+            }
+        }
+
+        let mut satellites: Vec<Vec<usize>> = vec![vec![]; names.len()];
+        for (satellite, center) in centers.iter().enumerate() {
+            if let Some(center) = center {
+                satellites[*center].push(satellite);
+            }
+        }
+        for bodies in satellites.iter_mut() {
+            bodies.sort_by(|a, b| names[*a].cmp(&names[*b]));
+        }
+
+        let roots: Vec<usize> = (0..names.len())
+            .filter(|&body| centers[body].is_none())
+            .collect();
+        let root = match roots.len() {
+            0 if names.is_empty() => return Err(OrbitError::InvalidOrbit(String::new())),
+            0 => return Err(OrbitError::Cycle(find_cycle(&names, &centers, 0))),
+            1 => roots[0],
+            _ => {
+                let mut roots: Vec<String> = roots.iter().map(|&r| names[r].clone()).collect();
+                roots.sort();
+                return Err(OrbitError::MultipleRoots(roots));
+            }
+        };
+
+        let mut depths: Vec<Option<usize>> = vec![None; names.len()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        depths[root] = Some(0);
+        queue.push_back(root);
+        while let Some(body) = queue.pop_front() {
+            for &satellite in satellites[body].iter() {
+                depths[satellite] = depths[body].map(|depth| depth + 1);
+                queue.push_back(satellite);
             }
         }
-        planet_paths.push(path);
+
+        if let Some(unreached) = depths.iter().position(|depth| depth.is_none()) {
+            return Err(OrbitError::Cycle(find_cycle(&names, &centers, unreached)));
+        }
+
+        Ok(OrbitTree {
+            names,
+            indices,
+            centers,
+            satellites,
+            depths: depths.into_iter().flatten().collect(),
+            root,
+        })
+    }
+
+    fn index(&self, body: &str) -> Result<usize, OrbitError> {
+        match self.indices.get(body) {
+            Some(&ind) => Ok(ind),
+            None => Err(OrbitError::UnknownBody(body.to_string())),
+        }
+    }
+
+    fn center(&self, body: &str) -> Result<&str, OrbitError> {
+        match self.centers[self.index(body)?] {
+            Some(center) => Ok(&self.names[center]),
+            None => Err(OrbitError::NoCenter(body.to_string())),
+        }
+    }
+
+    fn depth(&self, body: &str) -> Result<usize, OrbitError> {
+        Ok(self.depths[self.index(body)?])
+    }
+
+    fn total_orbits(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    fn lowest_common_ancestor(&self, a: &str, b: &str) -> Result<&str, OrbitError> {
+        let mut a = self.index(a)?;
+        let mut b = self.index(b)?;
+        while self.depths[a] > self.depths[b] {
+            a = self.centers[a].unwrap_or(self.root);
+        }
+        while self.depths[b] > self.depths[a] {
+            b = self.centers[b].unwrap_or(self.root);
+        }
+        while a != b {
+            a = self.centers[a].unwrap_or(self.root);
+            b = self.centers[b].unwrap_or(self.root);
+        }
+        Ok(&self.names[a])
     }
 
-    let you_path = &planet_paths[0];
-    let san_path = &planet_paths[1];
+    fn distance(&self, a: &str, b: &str) -> Result<usize, OrbitError> {
+        let ancestor = self.depth(self.lowest_common_ancestor(a, b)?)?;
+        Ok(self.depth(a)? + self.depth(b)? - 2 * ancestor)
+    }
 
-    let mut n = 0;
-    for i in 0..you_path.len() {
-        let path = &you_path[i];
-        match san_path.iter().position(|x| x == path) {
-            Some(x) => {
-                n = x + i;
-                break;
+    fn transfers(&self, from: &str, to: &str) -> Result<usize, OrbitError> {
+        self.distance(self.center(from)?, self.center(to)?)
+    }
+
+    fn path(&self, from: &str, to: &str) -> Result<Vec<&str>, OrbitError> {
+        let ancestor = self.index(self.lowest_common_ancestor(from, to)?)?;
+        let mut up = vec![];
+        let mut down = vec![];
+        for (body, path) in [(from, &mut up), (to, &mut down)].iter_mut() {
+            let mut body = self.index(body)?;
+            while body != ancestor {
+                path.push(self.names[body].as_str());
+                body = self.centers[body].unwrap_or(ancestor);
+            }
+        }
+        up.push(self.names[ancestor].as_str());
+        up.extend(down.into_iter().rev());
+        Ok(up)
+    }
+
+    fn to_dot(&self, highlight: Option<(&str, &str)>) -> Result<String, OrbitError> {
+        let path = match highlight {
+            Some((from, to)) => self.path(from, to)?,
+            None => vec![],
+        };
+        let on_path = |body: usize| path.contains(&self.names[body].as_str());
+
+        let mut dot = String::from("digraph orbits {\n    rankdir=BT;\n");
+        for body in self.subtree(&self.names[self.root])? {
+            let ind = self.index(body)?;
+            if on_path(ind) {
+                dot.push_str(&format!("    \"{}\" [color=red, fontcolor=red];\n", body));
+            }
+            if let Some(center) = self.centers[ind] {
+                let style = if on_path(ind) && on_path(center) {
+                    " [color=red, penwidth=2]"
+                } else {
+                    ""
+                };
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\"{};\n",
+                    body, self.names[center], style
+                ));
             }
-            None => continue,
         }
+        dot.push_str("}\n");
+        Ok(dot)
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut stack = vec![self.root];
+        while let Some(body) = stack.pop() {
+            text.push_str(&"  ".repeat(self.depths[body]));
+            text.push_str(&self.names[body]);
+            text.push('\n');
+            stack.extend(self.satellites[body].iter().rev());
+        }
+        text
     }
-    n
+
+    fn subtree(&self, body: &str) -> Result<Vec<&str>, OrbitError> {
+        let mut bodies = vec![];
+        let mut stack = vec![self.index(body)?];
+        while let Some(body) = stack.pop() {
+            bodies.push(self.names[body].as_str());
+            stack.extend(self.satellites[body].iter().rev());
+        }
+        Ok(bodies)
+    }
+}
+
+fn find_cycle(names: &[String], centers: &[Option<usize>], start: usize) -> Vec<String> {
+    let mut seen: Vec<bool> = vec![false; names.len()];
+    let mut body = start;
+    while !seen[body] {
+        seen[body] = true;
+        body = centers[body].unwrap_or(body);
+    }
+
+    let mut cycle = vec![names[body].clone()];
+    let mut next = centers[body].unwrap_or(body);
+    while next != body {
+        cycle.push(names[next].clone());
+        next = centers[next].unwrap_or(body);
+    }
+    cycle.reverse();
+
+    let first = (0..cycle.len()).min_by_key(|&ind| &cycle[ind]).unwrap_or(0);
+    cycle.rotate_left(first);
+    cycle
 }
 
 #[cfg(test)]
 mod tests {
-    use super::get_shorter_path;
-    use super::get_total_of_orbits;
+    use super::OrbitError;
+    use super::OrbitTree;
 
     #[test]
     fn it_should_compute_the_number_of_orbits() {
         let planets: Vec<&str> = vec![
             "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L",
         ];
-        assert_eq!(get_total_of_orbits(&planets), 42);
+        assert_eq!(OrbitTree::parse(&planets).unwrap().total_orbits(), 42);
     }
 
     #[test]
@@ -96,6 +307,107 @@ mod tests {
             "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L", "K)YOU",
             "I)SAN",
         ];
-        assert_eq!(get_shorter_path(&planets), 4);
+        assert_eq!(
+            OrbitTree::parse(&planets).unwrap().transfers("YOU", "SAN"),
+            Ok(4)
+        );
+    }
+
+    #[test]
+    fn it_should_answer_queries_on_any_bodies() {
+        let planets: Vec<&str> = vec![
+            "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L",
+        ];
+        let tree = OrbitTree::parse(&planets).unwrap();
+
+        assert_eq!(tree.depth("L"), Ok(7));
+        assert_eq!(tree.depth("COM"), Ok(0));
+        assert_eq!(tree.lowest_common_ancestor("H", "L"), Ok("B"));
+        assert_eq!(tree.lowest_common_ancestor("F", "K"), Ok("E"));
+        assert_eq!(tree.distance("H", "F"), Ok(6));
+        assert_eq!(tree.transfers("H", "L"), Ok(6));
+        assert_eq!(tree.subtree("E"), Ok(vec!["E", "F", "J", "K", "L"]));
+        assert_eq!(
+            tree.depth("X"),
+            Err(OrbitError::UnknownBody(String::from("X")))
+        );
+        assert_eq!(
+            tree.transfers("COM", "L"),
+            Err(OrbitError::NoCenter(String::from("COM")))
+        );
+    }
+
+    #[test]
+    fn it_should_find_the_path_between_bodies() {
+        let planets: Vec<&str> = vec!["COM)B", "B)C", "C)D", "B)E", "D)YOU", "E)SAN"];
+        let tree = OrbitTree::parse(&planets).unwrap();
+
+        assert_eq!(
+            tree.path("YOU", "SAN"),
+            Ok(vec!["YOU", "D", "C", "B", "E", "SAN"])
+        );
+        assert_eq!(tree.path("C", "D"), Ok(vec!["C", "D"]));
+    }
+
+    #[test]
+    fn it_should_export_to_dot() {
+        let planets: Vec<&str> = vec!["COM)B", "B)C", "B)YOU", "C)SAN"];
+        let tree = OrbitTree::parse(&planets).unwrap();
+
+        assert_eq!(
+            tree.to_dot(None),
+            Ok(String::from(
+                "digraph orbits {\n    rankdir=BT;\n    \"B\" -> \"COM\";\n    \"C\" -> \"B\";\n    \"SAN\" -> \"C\";\n    \"YOU\" -> \"B\";\n}\n"
+            ))
+        );
+        assert_eq!(
+            tree.to_dot(Some(("YOU", "SAN"))),
+            Ok(String::from(
+                "digraph orbits {\n    rankdir=BT;\n    \"B\" [color=red, fontcolor=red];\n    \"B\" -> \"COM\";\n    \"C\" [color=red, fontcolor=red];\n    \"C\" -> \"B\" [color=red, penwidth=2];\n    \"SAN\" [color=red, fontcolor=red];\n    \"SAN\" -> \"C\" [color=red, penwidth=2];\n    \"YOU\" [color=red, fontcolor=red];\n    \"YOU\" -> \"B\" [color=red, penwidth=2];\n}\n"
+            ))
+        );
+    }
+
+    #[test]
+    fn it_should_export_to_indented_text() {
+        let planets: Vec<&str> = vec!["B)C", "COM)B", "B)D", "C)E"];
+        assert_eq!(
+            OrbitTree::parse(&planets).unwrap().to_text(),
+            "COM\n  B\n    C\n      E\n    D\n"
+        );
+    }
+
+    #[test]
+    fn it_should_report_malformed_maps() {
+        assert_eq!(
+            OrbitTree::parse(&["COM)B", "B)C", "X)Y"]).err(),
+            Some(OrbitError::MultipleRoots(vec![
+                String::from("COM"),
+                String::from("X")
+            ]))
+        );
+        assert_eq!(
+            OrbitTree::parse(&["COM)B", "C)D", "D)E", "E)C"]).err(),
+            Some(OrbitError::Cycle(vec![
+                String::from("C"),
+                String::from("D"),
+                String::from("E")
+            ]))
+        );
+        assert_eq!(
+            OrbitTree::parse(&["A)B", "B)A"]).err(),
+            Some(OrbitError::Cycle(vec![
+                String::from("A"),
+                String::from("B")
+            ]))
+        );
+        assert_eq!(
+            OrbitTree::parse(&["COM)B", "C)B"]).err(),
+            Some(OrbitError::MultipleCenters(String::from("B")))
+        );
+        assert_eq!(
+            OrbitTree::parse(&["COM)B", "BC"]).err(),
+            Some(OrbitError::InvalidOrbit(String::from("BC")))
+        );
     }
 }
@@ -1,128 +1,514 @@
-use std::{fs::read_to_string, io};
+use std::{env, fmt, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let file = read_to_string("src/bin/day08-space-image-format/input.txt")?;
-    let input = file.trim();
+    let mut command = None;
+    let mut size = (25, 6);
+    let mut transparency = Transparency::Error;
+    for arg in env::args().skip(1) {
+        if COMMANDS.contains(&arg.as_str()) && command.is_none() {
+            command = Some(arg);
+        } else if arg.contains('x') {
+            size = parse_size(&arg)?;
+        } else if arg.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+            transparency = parse_fill(&arg)?;
+        } else {
+            return Err(invalid_input(format!("Unknown command {}", arg)));
+        }
+    }
+    let image = SpaceImage::decode(file.trim(), size.0, size.1).map_err(invalid_data)?;
 
-    println!("{}", compute_part1_result(input, &6, &25));
-    println!("{}", compute_part2_result(input, &6, &25));
+    match command.as_deref() {
+        Some("render") => {
+            let pixels = image.compose(transparency).map_err(invalid_data)?;
+            print!("{}", render(&pixels, image.width, &PALETTE));
+        }
+        Some("stats") => {
+            for (layer, histogram) in image.histograms().iter().enumerate() {
+                let counts: Vec<String> = histogram.iter().map(|n| n.to_string()).collect();
+                println!("{:>3}: {}", layer, counts.join(" "));
+            }
+        }
+        Some("provenance") => {
+            for row in image.provenance().chunks(image.width) {
+                let layers: Vec<String> = row
+                    .iter()
+                    .map(|layer| match layer {
+                        Some(layer) => format!("{:>3}", layer),
+                        None => String::from("  -"),
+                    })
+                    .collect();
+                println!("{}", layers.join(""));
+            }
+        }
+        Some("encode") => {
+            let pixels = image.compose(transparency).map_err(invalid_data)?;
+            let composed = SpaceImage::from_pixels(&pixels, image.width, image.height)
+                .map_err(invalid_data)?;
+            println!("{}", composed.encode());
+        }
+        _ => {
+            println!("{}", compute_part1_result(&image));
+            println!(
+                "{}",
+                compute_part2_result(&image, transparency).map_err(invalid_data)?
+            );
+        }
+    }
 
     Ok(())
 }
 
-fn compute_part1_result(input: &str, height: &i32, width: &i32) -> usize {
-    let layer = find_layer_with_fewest_0_digits(input, height, width);
-    let number_of_1_digits = layer.iter().filter(|&c| *c == '1').count();
-    let number_of_2_digits = layer.iter().filter(|&c| *c == '2').count();
-    number_of_1_digits * number_of_2_digits
+fn invalid_data(error: SifError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
 }
 
-fn compute_part2_result(input: &str, height: &i32, width: &i32) -> String {
-    let image = merge_layers(input, height, width);
-    let mut output: String = String::new();
-    for h in 0..height.clone() {
-        output.push('8');
-        for w in 0..width.clone() {
-            let ind: usize = (h * width + w) as usize;
-            output.push(image[ind]);
-        }
-        output.push('\n');
+const COMMANDS: [&str; 4] = ["render", "stats", "provenance", "encode"];
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn parse_size(size: &str) -> io::Result<(usize, usize)> {
+    let mut parts = size.splitn(2, 'x').map(|n| n.parse::<usize>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(width)), Some(Ok(height))) => Ok((width, height)),
+        _ => Err(invalid_input(format!("Invalid size {}", size))),
     }
-    output
 }
 
-fn find_layer_with_fewest_0_digits(input: &str, height: &i32, width: &i32) -> Vec<char> {
-    let layers = parse_layers(input, height, width);
-    let mut iter = layers.iter();
-    let mut min_number_of_0_digits = usize::max_value();
-    let mut min_number_of_0_digits_layer: Vec<char> = vec![];
-    loop {
-        match iter.next() {
-            Some(layer) => {
-                let number_of_0_digits = layer.iter().filter(|&c| *c == '0').count();
-                if number_of_0_digits < min_number_of_0_digits {
-                    min_number_of_0_digits = number_of_0_digits;
-                    min_number_of_0_digits_layer = layer.clone();
-                }
+fn parse_fill(color: &str) -> io::Result<Transparency> {
+    match color.parse::<u8>() {
+        Ok(color) if color <= 9 => Ok(Transparency::Fill(color)),
+        _ => Err(invalid_input(format!("Invalid fill color {}", color))),
+    }
+}
+
+fn compute_part1_result(image: &SpaceImage) -> usize {
+    image.checksum(0, 1, 2).unwrap_or(0)
+}
+
+fn compute_part2_result(
+    image: &SpaceImage,
+    transparency: Transparency,
+) -> Result<String, SifError> {
+    let pixels = image.compose(transparency)?;
+    let ink: Vec<Vec<bool>> = pixels
+        .chunks(image.width)
+        .map(|row| row.iter().map(|&d| d == WHITE).collect())
+        .collect();
+    match read_letters(&ink) {
+        Some(letters) => Ok(letters),
+        None => Ok(render(&pixels, image.width, &PALETTE)),
+    }
+}
+
+const WHITE: u8 = 1;
+const TRANSPARENT: u8 = 2;
+const PALETTE: [char; 10] = ['#', ' ', '.', '3', '4', '5', '6', '7', '8', '9'];
+
+#[derive(Debug, Clone, PartialEq)]
+enum SifError {
+    InvalidDimensions { width: usize, height: usize },
+    InvalidLength { length: usize, layer_size: usize },
+    InvalidDigit(char),
+    TransparentPixel { x: usize, y: usize },
+}
+
+impl fmt::Display for SifError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SifError::InvalidDimensions { width, height } => {
+                write!(f, "Invalid dimensions {}x{}", width, height)
+            }
+            SifError::InvalidLength { length, layer_size } => write!(
+                f,
+                "Input length {} is not a multiple of layer size {}",
+                length, layer_size
+            ),
+            SifError::InvalidDigit(c) => write!(f, "Invalid digit {}", c),
+            SifError::TransparentPixel { x, y } => {
+                write!(f, "Pixel {},{} is transparent on every layer", x, y)
             }
-            _ => break,
         }
     }
-    min_number_of_0_digits_layer
-}
-
-fn merge_layers(input: &str, height: &i32, width: &i32) -> Vec<char> {
-    let layers = parse_layers(input, height, width);
-    let mut image = Vec::new();
-    for h in 0..height.clone() {
-        for w in 0..width.clone() {
-            let mut iter = layers.iter();
-            loop {
-                match iter.next() {
-                    Some(layer) => {
-                        let ind: usize = (h * width + w) as usize;
-                        match layer[ind] {
-                            '0' => {
-                                image.push('8');
-                                break;
-                            }
-                            '1' => {
-                                image.push(' ');
-                                break;
-                            }
-                            _ => continue,
-                        }
-                    }
-                    _ => {
-                        println!("All layers are transparent ! Fallback to white");
-                        image.push(' ');
-                    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transparency {
+    Error,
+    Fill(u8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SpaceImage {
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<u8>>,
+}
+
+impl SpaceImage {
+    fn decode(input: &str, width: usize, height: usize) -> Result<Self, SifError> {
+        let layer_size = width * height;
+        if layer_size == 0 {
+            return Err(SifError::InvalidDimensions { width, height });
+        }
+
+        let digits = input
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(digit) => Ok(digit as u8),
+                None => Err(SifError::InvalidDigit(c)),
+            })
+            .collect::<Result<Vec<u8>, SifError>>()?;
+
+        if digits.is_empty() || digits.len() % layer_size != 0 {
+            return Err(SifError::InvalidLength {
+                length: digits.len(),
+                layer_size,
+            });
+        }
+
+        Ok(SpaceImage {
+            width,
+            height,
+            layers: digits
+                .chunks(layer_size)
+                .map(|layer| layer.to_vec())
+                .collect(),
+        })
+    }
+
+    fn from_pixels(pixels: &[u8], width: usize, height: usize) -> Result<Self, SifError> {
+        let input: String = pixels.iter().map(|d| char::from(b'0' + d)).collect();
+        SpaceImage::decode(&input, width, height)
+    }
+
+    fn encode(&self) -> String {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.iter().map(|d| char::from(b'0' + d)))
+            .collect()
+    }
+
+    fn compose(&self, transparency: Transparency) -> Result<Vec<u8>, SifError> {
+        (0..self.width * self.height)
+            .map(|ind| {
+                match self
+                    .layers
+                    .iter()
+                    .map(|layer| layer[ind])
+                    .find(|&d| d != TRANSPARENT)
+                {
+                    Some(digit) => Ok(digit),
+                    None => match transparency {
+                        Transparency::Fill(color) => Ok(color),
+                        Transparency::Error => Err(SifError::TransparentPixel {
+                            x: ind % self.width,
+                            y: ind / self.width,
+                        }),
+                    },
                 }
-            }
+            })
+            .collect()
+    }
+}
+
+impl SpaceImage {
+    fn histogram(&self, layer: usize) -> [usize; 10] {
+        let mut histogram = [0; 10];
+        for &d in self.layers[layer].iter() {
+            histogram[d as usize] += 1;
         }
+        histogram
+    }
+
+    fn histograms(&self) -> Vec<[usize; 10]> {
+        (0..self.layers.len())
+            .map(|layer| self.histogram(layer))
+            .collect()
+    }
+
+    fn select_layer<K: Ord>(&self, key: impl Fn(&[usize; 10]) -> K) -> Option<usize> {
+        self.histograms()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, histogram)| key(histogram))
+            .map(|(layer, _)| layer)
+    }
+
+    fn checksum(&self, fewest: u8, a: u8, b: u8) -> Option<usize> {
+        let layer = self.select_layer(|histogram| histogram[fewest as usize])?;
+        let histogram = self.histogram(layer);
+        Some(histogram[a as usize] * histogram[b as usize])
+    }
+
+    fn provenance(&self) -> Vec<Option<usize>> {
+        (0..self.width * self.height)
+            .map(|ind| {
+                self.layers
+                    .iter()
+                    .position(|layer| layer[ind] != TRANSPARENT)
+            })
+            .collect()
     }
-    image
 }
 
-fn parse_layers(input: &str, height: &i32, width: &i32) -> Vec<Vec<char>> {
-    let mut layers = Vec::new();
-    let mut chars = input.chars();
-    'outer: loop {
-        let mut layer = Vec::new();
-        for _ in 0..height.clone() {
-            for _ in 0..width.clone() {
-                match chars.next() {
-                    Some(character) => layer.push(character),
-                    _ => break 'outer,
-                }
-            }
+fn render(pixels: &[u8], width: usize, palette: &[char]) -> String {
+    let mut output: String = String::new();
+    for row in pixels.chunks(width) {
+        for &d in row {
+            output.push(palette.get(d as usize).copied().unwrap_or('?'));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+const GLYPHS: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn read_letters(pixels: &[Vec<bool>]) -> Option<String> {
+    let rows: Vec<&Vec<bool>> = pixels.iter().filter(|row| row.contains(&true)).collect();
+    if rows.len() != 6 {
+        return None;
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_ink = |x: usize| rows.iter().any(|row| row.get(x) == Some(&true));
+
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < width {
+        if !is_ink(x) {
+            x += 1;
+            continue;
         }
-        layers.push(layer);
+
+        let start = x;
+        while x < width && is_ink(x) {
+            x += 1;
+        }
+
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (start..x)
+                    .map(|col| {
+                        if row.get(col) == Some(&true) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        letters.push(recognize_glyph(&glyph)?);
+    }
+
+    if letters.is_empty() {
+        return None;
     }
-    layers
+
+    Some(letters)
+}
+
+fn recognize_glyph(glyph: &[String]) -> Option<char> {
+    GLYPHS.iter().find_map(|(letter, pattern)| {
+        let left = (0..pattern[0].len())
+            .find(|&col| pattern.iter().any(|line| &line[col..=col] == "#"))
+            .unwrap_or(0);
+        let right = (0..pattern[0].len())
+            .rev()
+            .find(|&col| pattern.iter().any(|line| &line[col..=col] == "#"))
+            .unwrap_or(0);
+
+        if pattern
+            .iter()
+            .zip(glyph.iter())
+            .all(|(line, row)| line[left..=right] == row[..])
+        {
+            Some(*letter)
+        } else {
+            None
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::merge_layers;
-    use super::parse_layers;
+    use super::compute_part1_result;
+    use super::compute_part2_result;
+    use super::parse_fill;
+    use super::parse_size;
+    use super::read_letters;
+    use super::render;
+    use super::SifError;
+    use super::SpaceImage;
+    use super::Transparency;
+    use super::PALETTE;
 
     #[test]
     fn it_should_parse_input_layers() {
-        let layers = parse_layers("123456789012", &2, &3);
+        let image = SpaceImage::decode("123456789012", 3, 2).unwrap();
+
+        assert_eq!(
+            image.layers,
+            vec![vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]]
+        );
+        assert_eq!(compute_part1_result(&image), 1);
+    }
+
+    #[test]
+    fn it_should_merge_layers() {
+        let image = SpaceImage::decode("0222112222120000", 2, 2).unwrap();
+        let pixels = image.compose(Transparency::Error).unwrap();
+
+        assert_eq!(pixels, vec![0, 1, 1, 0]);
+        assert_eq!(render(&pixels, 2, &PALETTE), "# \n #\n");
+        assert_eq!(render(&[0, 8], 2, &PALETTE), "#8\n");
+    }
+
+    #[test]
+    fn it_should_parse_command_line_options() {
+        assert_eq!(parse_size("5x3").unwrap(), (5, 3));
+        assert!(parse_size("5x").is_err());
+        assert_eq!(parse_fill("9").unwrap(), Transparency::Fill(9));
+        assert!(parse_fill("10").is_err());
+        assert!(parse_fill("-1").is_err());
+    }
+
+    #[test]
+    fn it_should_validate_input() {
+        assert_eq!(
+            SpaceImage::decode("1234567", 3, 2),
+            Err(SifError::InvalidLength {
+                length: 7,
+                layer_size: 6
+            })
+        );
+        assert_eq!(
+            SpaceImage::decode("12345a", 3, 2),
+            Err(SifError::InvalidDigit('a'))
+        );
+        assert_eq!(
+            SpaceImage::decode("123456", 0, 2),
+            Err(SifError::InvalidDimensions {
+                width: 0,
+                height: 2
+            })
+        );
+    }
+
+    #[test]
+    fn it_should_handle_transparent_pixels() {
+        let image = SpaceImage::decode("2212", 2, 1).unwrap();
 
         assert_eq!(
-            layers,
+            image.compose(Transparency::Error),
+            Err(SifError::TransparentPixel { x: 1, y: 0 })
+        );
+        assert_eq!(image.compose(Transparency::Fill(5)), Ok(vec![1, 5]));
+    }
+
+    #[test]
+    fn it_should_encode_a_composed_image() {
+        let image = SpaceImage::decode("2922273722234522", 2, 2).unwrap();
+        let pixels = image.compose(Transparency::Error).unwrap();
+        let composed = SpaceImage::from_pixels(&pixels, 2, 2).unwrap();
+
+        assert_eq!(pixels, vec![4, 9, 3, 7]);
+        assert_eq!(image.encode(), "2922273722234522");
+        assert_eq!(composed.encode(), "4937");
+        assert_eq!(composed.compose(Transparency::Error), Ok(pixels));
+    }
+
+    #[test]
+    fn it_should_compute_layer_statistics() {
+        let image = SpaceImage::decode("001122222223091111", 3, 2).unwrap();
+
+        assert_eq!(
+            image.histograms(),
             vec![
-                vec!['1', '2', '3', '4', '5', '6'],
-                vec!['7', '8', '9', '0', '1', '2'],
+                [2, 2, 2, 0, 0, 0, 0, 0, 0, 0],
+                [0, 0, 5, 1, 0, 0, 0, 0, 0, 0],
+                [1, 4, 0, 0, 0, 0, 0, 0, 0, 1],
             ]
         );
+        assert_eq!(image.select_layer(|histogram| histogram[0]), Some(1));
+        assert_eq!(
+            image.select_layer(|histogram| std::cmp::Reverse(histogram[1])),
+            Some(2)
+        );
+        assert_eq!(image.checksum(0, 1, 2), Some(0));
+        assert_eq!(image.checksum(2, 0, 1), Some(4));
     }
 
     #[test]
-    fn it_should_merge_layers() {
-        let image = merge_layers("0222112222120000", &2, &2);
-        assert_eq!(image, vec!['8', ' ', ' ', '8']);
+    fn it_should_track_visible_pixel_provenance() {
+        let image = SpaceImage::decode("0222112222120000", 2, 2).unwrap();
+        assert_eq!(image.provenance(), vec![Some(0), Some(1), Some(2), Some(3)]);
+
+        let image = SpaceImage::decode("2212", 2, 1).unwrap();
+        assert_eq!(image.provenance(), vec![Some(1), None]);
+    }
+
+    #[test]
+    fn it_should_read_the_message_from_input() {
+        let file = std::fs::read_to_string("src/bin/day08-space-image-format/input.txt").unwrap();
+        let image = SpaceImage::decode(file.trim(), 25, 6).unwrap();
+
+        assert_eq!(compute_part1_result(&image), 1848);
+        assert_eq!(
+            compute_part2_result(&image, Transparency::Error),
+            Ok(String::from("FGJUZ"))
+        );
+    }
+
+    #[test]
+    fn it_should_read_letters() {
+        let pixels: Vec<Vec<bool>> = [
+            "#### #  #   ## #  # #    ",
+            "#    #  #    # #  # #    ",
+            "###  ####    # #  # #    ",
+            "#    #  #    # #  # #    ",
+            "#    #  # #  # #  # #    ",
+            "#    #  #  ##   ##  #### ",
+        ]
+        .iter()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect();
+
+        assert_eq!(read_letters(&pixels), Some(String::from("FHJUL")));
+    }
+
+    #[test]
+    fn it_should_not_read_unknown_letters() {
+        let pixels: Vec<Vec<bool>> = ["# #", " # ", "# #", " # ", "# #", " # "]
+            .iter()
+            .map(|row| row.chars().map(|c| c == '#').collect())
+            .collect();
+
+        assert_eq!(read_letters(&pixels), None);
     }
 }
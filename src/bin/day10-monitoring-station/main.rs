@@ -1,230 +1,366 @@
 use std::cmp::Ordering;
-use std::{fs::read_to_string, io};
+use std::collections::HashMap;
+use std::fs::{read_to_string, write};
+use std::{env, io};
 
 fn main() -> io::Result<()> {
     let file = read_to_string("src/bin/day10-monitoring-station/input.txt")?;
     let input: Vec<&str> = file.trim().split('\n').collect();
+    let field = AsteroidField::parse(&input);
 
-    let tuple = find_monitoring_station_asteroid(&input, Some(200));
+    match env::args().nth(1).as_deref() {
+        Some("heatmap") => {
+            for (asteroid, visible) in field.heatmap() {
+                println!("{},{} {}", asteroid.x, asteroid.y, visible);
+            }
+        }
+        Some("vaporize") => {
+            let station = match (env::args().nth(2), env::args().nth(3)) {
+                (Some(x), Some(y)) => Asteroid::new(x.parse().unwrap(), y.parse().unwrap()),
+                _ => field.best_station().expect("No asteroid in the field").0,
+            };
+            for (ind, asteroid) in field.vaporization_order(&station).iter().enumerate() {
+                println!("{} {},{}", ind + 1, asteroid.x, asteroid.y);
+            }
+        }
+        Some("render") => {
+            let vaporized = match env::args().skip(2).find(|arg| !arg.starts_with("--")) {
+                Some(count) => parse_count(&count)?,
+                None => 0,
+            };
+            let colors = env::args().any(|arg| arg == "--color");
+            let station = field.best_station().map(|(station, _)| station);
+            print!(
+                "{}",
+                HeatmapRenderer::new(&field, station, vaporized).to_text(colors)
+            );
+        }
+        Some("ppm") => {
+            let path = env::args().nth(2).expect("usage: ppm <file> [vaporized]");
+            let vaporized = match env::args().nth(3) {
+                Some(count) => parse_count(&count)?,
+                None => 0,
+            };
+            let station = field.best_station().map(|(station, _)| station);
+            write(
+                path,
+                HeatmapRenderer::new(&field, station, vaporized).to_ppm(8),
+            )?;
+        }
+        _ => {
+            let tuple = find_monitoring_station_asteroid(&input, Some(200));
 
-    println!("{}", tuple.0);
-    println!("{}", tuple.1);
+            println!("{}", tuple.0);
+            println!("{}", tuple.1);
+        }
+    }
 
     Ok(())
 }
 
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn parse_count(count: &str) -> io::Result<usize> {
+    count
+        .parse::<usize>()
+        .map_err(|_| invalid_input(format!("Invalid count {}", count)))
+}
+
 fn find_monitoring_station_asteroid(
-    universe: &Vec<&str>,
+    universe: &[&str],
     nth_asteroid_index: Option<usize>,
-) -> (usize, f64) {
-    let mut asteroids: Vec<Asteroid> = vec![];
-    for y in 0..universe.len() {
-        for x in 0..universe[y].len() {
-            if universe[y].chars().nth(x).unwrap() == '#' {
-                asteroids.push(Asteroid::new(x as f64, y as f64))
+) -> (usize, isize) {
+    let field = AsteroidField::parse(universe);
+    let (station, max_visible_asteroids) = match field.best_station() {
+        Some(best) => best,
+        None => return (0, 0),
+    };
+
+    let nth_asteroid = match nth_asteroid_index {
+        Some(index) => match index
+            .checked_sub(1)
+            .and_then(|ind| field.vaporization_order(&station).get(ind).copied())
+        {
+            Some(asteroid) => asteroid,
+            None => panic!("Asteroid not found !"),
+        },
+        None => Asteroid::new(0, 0),
+    };
+
+    (max_visible_asteroids, nth_asteroid.x * 100 + nth_asteroid.y)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AsteroidField {
+    width: usize,
+    height: usize,
+    asteroids: Vec<Asteroid>,
+}
+
+impl AsteroidField {
+    fn parse(universe: &[&str]) -> Self {
+        let mut asteroids: Vec<Asteroid> = vec![];
+        for (y, line) in universe.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    asteroids.push(Asteroid::new(x as isize, y as isize))
+                }
             }
         }
+
+        AsteroidField {
+            width: universe.iter().map(|line| line.len()).max().unwrap_or(0),
+            height: universe.len(),
+            asteroids,
+        }
     }
 
-    let nth_asteroid_index = match nth_asteroid_index {
-        Some(number) => number,
-        _ => usize::max_value(),
-    };
-    let mut max_visible_asteroids = usize::min_value();
-    let mut nth_asteroid = Asteroid::new(0.0, 0.0);
-    for asteroid1 in asteroids.iter() {
-        let mut vertices: Vec<Vertice> = vec![];
-        for asteroid2 in asteroids.iter() {
-            if asteroid1 == asteroid2 {
+    fn vertices(&self, station: &Asteroid) -> Vec<Vertice> {
+        let mut vertices: HashMap<(isize, isize), Vertice> = HashMap::new();
+        for asteroid in self.asteroids.iter() {
+            if asteroid == station {
                 continue;
             }
 
-            let mut found = false;
-            let mut vertice = Vertice::new(asteroid1, asteroid2);
-            for previous_vertice in vertices.iter_mut() {
-                if previous_vertice == &mut vertice {
-                    found = true;
-                    previous_vertice
-                        .add_asteroid(Asteroid::new(asteroid2.x, asteroid2.y), asteroid1);
+            let vertice = Vertice::new(station, asteroid);
+            vertices
+                .entry(vertice.direction)
+                .or_insert(vertice)
+                .add_asteroid(*asteroid, station);
+        }
+
+        let mut vertices: Vec<Vertice> = vertices.into_values().collect();
+        vertices.sort();
+        vertices
+    }
+
+    fn visibility(&self, station: &Asteroid) -> usize {
+        self.vertices(station).len()
+    }
+
+    fn heatmap(&self) -> Vec<(Asteroid, usize)> {
+        self.asteroids
+            .iter()
+            .map(|asteroid| (*asteroid, self.visibility(asteroid)))
+            .collect()
+    }
+
+    fn best_station(&self) -> Option<(Asteroid, usize)> {
+        self.heatmap()
+            .into_iter()
+            .fold(None, |best, (asteroid, visible)| match best {
+                Some((_, max)) if max >= visible => best,
+                _ => Some((asteroid, visible)),
+            })
+    }
+
+    fn vaporization_order(&self, station: &Asteroid) -> Vec<Asteroid> {
+        let mut vertices = self.vertices(station);
+        let mut destroyed_asteroids: Vec<Asteroid> = vec![];
+        while vertices.iter().any(|vertice| !vertice.asteroids.is_empty()) {
+            for vertice in vertices.iter_mut() {
+                if let Some(vaporised_asteroid) = vertice.destroy_asteroid() {
+                    destroyed_asteroids.push(vaporised_asteroid);
                 }
             }
+        }
+        destroyed_asteroids
+    }
+}
 
-            if !found {
-                let mut vertice = Vertice::new(asteroid1, asteroid2);
-                vertice.add_asteroid(Asteroid::new(asteroid2.x, asteroid2.y), asteroid1);
-                vertices.push(vertice);
-            }
+struct HeatmapRenderer<'a> {
+    field: &'a AsteroidField,
+    heat: HashMap<Asteroid, usize>,
+    max_heat: usize,
+    station: Option<Asteroid>,
+    vaporized: Vec<Asteroid>,
+}
+
+impl<'a> HeatmapRenderer<'a> {
+    fn new(field: &'a AsteroidField, station: Option<Asteroid>, vaporized: usize) -> Self {
+        let heat: HashMap<Asteroid, usize> = field.heatmap().into_iter().collect();
+        let vaporized = match station {
+            Some(station) => field
+                .vaporization_order(&station)
+                .into_iter()
+                .take(vaporized)
+                .collect(),
+            None => vec![],
+        };
+
+        HeatmapRenderer {
+            field,
+            max_heat: heat.values().copied().max().unwrap_or(0),
+            heat,
+            station,
+            vaporized,
         }
+    }
 
-        if vertices.len() > max_visible_asteroids {
-            max_visible_asteroids = vertices.len();
+    fn heat_color(&self, asteroid: &Asteroid) -> (u8, u8, u8) {
+        let heat = self.heat.get(asteroid).copied().unwrap_or(0);
+        let ratio = heat * 255 / self.max_heat.max(1);
+        (ratio as u8, 64, (255 - ratio) as u8)
+    }
 
-            if max_visible_asteroids >= nth_asteroid_index {
-                vertices.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    fn to_text(&self, colors: bool) -> String {
+        let cell_width = self.max_heat.max(self.vaporized.len()).to_string().len() + 2;
+
+        let mut output = String::new();
+        for y in 0..self.field.height as isize {
+            for x in 0..self.field.width as isize {
+                let asteroid = Asteroid::new(x, y);
+                let (label, color) = if !self.heat.contains_key(&asteroid) {
+                    (String::from("."), None)
+                } else if Some(asteroid) == self.station {
+                    (String::from("S"), Some((255, 255, 255)))
+                } else if let Some(ind) = self.vaporized.iter().position(|a| *a == asteroid) {
+                    (format!("*{}", ind + 1), Some((255, 255, 0)))
+                } else {
+                    (
+                        self.heat[&asteroid].to_string(),
+                        Some(self.heat_color(&asteroid)),
+                    )
+                };
 
-                let mut destroyed_asteroids: Vec<Asteroid> = vec![];
-                while destroyed_asteroids.len() < nth_asteroid_index {
-                    for vertice in vertices.iter_mut() {
-                        let vaporised_asteroid = vertice.destroy_asteroid().unwrap();
-                        destroyed_asteroids.push(vaporised_asteroid);
-                        if destroyed_asteroids.len() >= nth_asteroid_index {
-                            break;
-                        }
+                let cell = format!("{:>width$}", label, width = cell_width);
+                match color {
+                    Some((r, g, b)) if colors => {
+                        output.push_str(&format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, cell))
                     }
+                    _ => output.push_str(&cell),
                 }
-                nth_asteroid = match destroyed_asteroids.pop() {
-                    Some(a) => a,
-                    _ => panic!("Asteroid not found !"),
-                };
             }
+            output.push('\n');
         }
+        output
     }
 
-    (
-        max_visible_asteroids,
-        nth_asteroid.x * 100.0 + nth_asteroid.y,
-    )
+    fn to_ppm(&self, scale: usize) -> String {
+        let mut output = format!(
+            "P3\n{} {}\n255\n",
+            self.field.width * scale,
+            self.field.height * scale
+        );
+        for y in 0..self.field.height * scale {
+            let row: Vec<String> = (0..self.field.width * scale)
+                .map(|x| {
+                    let asteroid = Asteroid::new((x / scale) as isize, (y / scale) as isize);
+                    let (r, g, b) = if !self.heat.contains_key(&asteroid) {
+                        (0, 0, 0)
+                    } else if Some(asteroid) == self.station {
+                        (255, 255, 255)
+                    } else if let Some(ind) = self.vaporized.iter().position(|a| *a == asteroid) {
+                        let fade = (ind * 191 / self.vaporized.len().max(1)) as u8;
+                        (255, 255 - fade, 0)
+                    } else {
+                        self.heat_color(&asteroid)
+                    };
+                    format!("{} {} {}", r, g, b)
+                })
+                .collect();
+            output.push_str(&row.join(" "));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+fn greatest_common_divisor(a: isize, b: isize) -> isize {
+    if b == 0 {
+        return a.abs();
+    }
+    greatest_common_divisor(b, a % b)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Asteroid {
-    x: f64,
-    y: f64,
+    x: isize,
+    y: isize,
 }
 
 impl Asteroid {
-    fn new(x: f64, y: f64) -> Self {
-        Asteroid { x: x, y: y }
-    }
-
-    fn partial_cmp(&self, other: &Self, origin: &Asteroid) -> Option<Ordering> {
-        if self.manhattan_distance(origin) > other.manhattan_distance(origin) {
-            return Some(Ordering::Less);
-        }
-        Some(Ordering::Greater)
+    fn new(x: isize, y: isize) -> Self {
+        Asteroid { x, y }
     }
 
-    fn manhattan_distance(&self, origin: &Asteroid) -> f64 {
+    fn manhattan_distance(&self, origin: &Asteroid) -> isize {
         (origin.x - self.x).abs() + (origin.y - self.y).abs()
     }
 }
 
-impl PartialEq for Asteroid {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
-    }
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Vertice {
-    a: f64,
-    dx: bool,
-    dy: bool,
+    direction: (isize, isize),
     asteroids: Vec<Asteroid>,
 }
 
 impl Vertice {
     fn new(asteroid1: &Asteroid, asteroid2: &Asteroid) -> Self {
+        let dx = asteroid2.x - asteroid1.x;
+        let dy = asteroid2.y - asteroid1.y;
+        let divisor = greatest_common_divisor(dx, dy).max(1);
         Vertice {
-            a: (asteroid1.y - asteroid2.y) / (asteroid2.x - asteroid1.x),
-            dx: if asteroid1.x < asteroid2.x {
-                true
-            } else {
-                false
-            },
-            dy: if asteroid1.y < asteroid2.y {
-                true
-            } else {
-                false
-            },
+            direction: (dx / divisor, dy / divisor),
             asteroids: vec![],
         }
     }
 
-    fn add_asteroid(&mut self, asteroid: Asteroid, origin: &Asteroid) -> () {
+    fn add_asteroid(&mut self, asteroid: Asteroid, origin: &Asteroid) {
         self.asteroids.push(asteroid);
-        self.asteroids
-            .sort_by(|a, b| a.partial_cmp(b, origin).unwrap());
+        self.asteroids.sort_by(|a, b| {
+            b.manhattan_distance(origin)
+                .cmp(&a.manhattan_distance(origin))
+        });
     }
 
     fn destroy_asteroid(&mut self) -> Option<Asteroid> {
         self.asteroids.pop()
     }
+
+    fn half(&self) -> u8 {
+        let (dx, dy) = self.direction;
+        if dx > 0 || (dx == 0 && dy < 0) {
+            0
+        } else {
+            1
+        }
+    }
 }
 
 impl Eq for Vertice {}
 
 impl PartialEq for Vertice {
     fn eq(&self, other: &Self) -> bool {
-        self.a == other.a && self.dx == other.dx && self.dy == other.dy
+        self.direction == other.direction
     }
 }
 
 impl PartialOrd for Vertice {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if (self.dx != other.dx) || (self.dy != other.dy) {
-            /*
-                   d1x   | d1y   | d2x   | d2y
-                /  true  | false |
-                \                | true  | true
-                 / true  | false |
-                /                | false | true
-                \/ true  | false | false | false
-
-
-                /\ true  | true  | false | trues
-                \                | false | false
-                 \ true  | true  |
-                \                | false | false
-                /  false | true  |
-            */
-            if (self.dx == true && (self.dy == false || (other.dx != true || other.dy != false)))
-                || (self.dy == true && other.dx == false && other.dy == false)
-            {
-                return Some(Ordering::Less);
-            }
-            /*
-                   d1x   | d1y   | d2x   | d2y
-                /                | true  | false
-                \  true  | true  |
-                 /               | true  | false
-                /  false | true  |
-                \/ false | false | true  | false
-
-
-                /\ false | true  | true  | true
-                \  false | false |
-                 \               | true  | true
-                \  false | false |
-                /                | false | true
-            */
-            return Some(Ordering::Greater);
-        }
-
-        /*
-          d1x   | d1y   | d2x   | d2y
-          true  | true  | true  | true
-          true  | false | true  | false
-          false | true  | false | true
-          false | false | false | false
-        */
-
-        if self.a > other.a {
-            return Some(Ordering::Less);
-        }
-
-        if self.a < other.a {
-            return Some(Ordering::Greater);
-        }
+        Some(self.cmp(other))
+    }
+}
 
-        Some(Ordering::Equal)
+impl Ord for Vertice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (ax, ay) = self.direction;
+        let (bx, by) = other.direction;
+        self.half()
+            .cmp(&other.half())
+            .then_with(|| (bx * ay).cmp(&(ax * by)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::find_monitoring_station_asteroid;
+    use super::parse_count;
     use super::Asteroid;
+    use super::AsteroidField;
+    use super::HeatmapRenderer;
     use super::Vertice;
 
     #[test]
@@ -314,23 +450,120 @@ mod tests {
     #[test]
     fn it_should_sort_vertices_1() {
         let mut vec = vec![
-            Vertice::new(&Asteroid::new(11.0, 13.0), &Asteroid::new(12.0, 7.0)),
-            Vertice::new(&Asteroid::new(11.0, 13.0), &Asteroid::new(10.0, 19.0)),
-            Vertice::new(&Asteroid::new(11.0, 13.0), &Asteroid::new(13.0, 2.0)),
+            Vertice::new(&Asteroid::new(11, 13), &Asteroid::new(12, 7)),
+            Vertice::new(&Asteroid::new(11, 13), &Asteroid::new(10, 19)),
+            Vertice::new(&Asteroid::new(11, 13), &Asteroid::new(13, 2)),
         ];
 
-        vec.sort_by(|a, b|  a.partial_cmp(b).unwrap());
+        vec.sort();
 
         assert_eq!(
             vec,
             vec![
-                Vertice::new(&Asteroid::new(11.0, 13.0), &Asteroid::new(12.0, 7.0)),
-                Vertice::new(&Asteroid::new(11.0, 13.0), &Asteroid::new(13.0, 2.0)),
-                Vertice::new(&Asteroid::new(11.0, 13.0), &Asteroid::new(10.0, 19.0)),
+                Vertice::new(&Asteroid::new(11, 13), &Asteroid::new(12, 7)),
+                Vertice::new(&Asteroid::new(11, 13), &Asteroid::new(13, 2)),
+                Vertice::new(&Asteroid::new(11, 13), &Asteroid::new(10, 19)),
             ]
         );
     }
 
+    #[test]
+    fn it_should_sort_vertices_clockwise_from_up() {
+        let origin = Asteroid::new(0, 0);
+        let mut vec: Vec<Vertice> = [(-1, -1), (0, 1), (1, 0), (0, -3), (-2, 0), (1, 1), (3, -1)]
+            .iter()
+            .map(|&(x, y)| Vertice::new(&origin, &Asteroid::new(x, y)))
+            .collect();
+
+        vec.sort();
+
+        assert_eq!(
+            vec.iter()
+                .map(|v| v.direction)
+                .collect::<Vec<(isize, isize)>>(),
+            vec![(0, -1), (3, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)]
+        );
+    }
+
+    #[test]
+    fn it_should_group_asteroids_on_the_same_line_exactly() {
+        let origin = Asteroid::new(0, 0);
+        assert_eq!(
+            Vertice::new(&origin, &Asteroid::new(3, -9)),
+            Vertice::new(&origin, &Asteroid::new(1000001, -3000003))
+        );
+        assert_ne!(
+            Vertice::new(&origin, &Asteroid::new(3, -9)),
+            Vertice::new(&origin, &Asteroid::new(1000000, -3000001))
+        );
+    }
+
+    #[test]
+    fn it_should_compute_the_visibility_heatmap() {
+        let field = AsteroidField::parse(&[".#..#", ".....", "#####", "....#", "...##"]);
+        let heatmap: Vec<usize> = field.heatmap().iter().map(|&(_, n)| n).collect();
+
+        assert_eq!(heatmap, vec![7, 7, 6, 7, 7, 7, 5, 7, 8, 7]);
+        assert_eq!(field.best_station(), Some((Asteroid::new(3, 4), 8)));
+        assert_eq!(field.visibility(&Asteroid::new(4, 2)), 5);
+    }
+
+    #[test]
+    fn it_should_render_the_heatmap_as_text() {
+        let field = AsteroidField::parse(&[".#..#", ".....", "#####", "....#", "...##"]);
+        let station = field.best_station().map(|(station, _)| station);
+
+        assert_eq!(
+            HeatmapRenderer::new(&field, station, 0).to_text(false),
+            "  .  7  .  .  7\n  .  .  .  .  .\n  6  7  7  7  5\n  .  .  .  .  7\n  .  .  .  S  7\n"
+        );
+        assert_eq!(
+            HeatmapRenderer::new(&field, station, 3).to_text(false),
+            "  .  7  .  . *2\n  .  .  .  .  .\n  6  7  7 *1 *3\n  .  .  .  .  7\n  .  .  .  S  7\n"
+        );
+        assert!(HeatmapRenderer::new(&field, station, 0)
+            .to_text(true)
+            .contains("\x1b[38;2;255;255;255m  S\x1b[0m"));
+    }
+
+    #[test]
+    fn it_should_render_the_heatmap_as_an_image() {
+        let field = AsteroidField::parse(&["#.", ".#"]);
+        let ppm = HeatmapRenderer::new(&field, Some(Asteroid::new(0, 0)), 0).to_ppm(1);
+
+        assert_eq!(ppm, "P3\n2 2\n255\n255 255 255 0 0 0\n0 0 0 255 64 0\n");
+    }
+
+    #[test]
+    fn it_should_vaporize_every_asteroid_from_any_station() {
+        let field = AsteroidField::parse(&[
+            ".#....#####...#..",
+            "##...##.#####..##",
+            "##...#...#.#####.",
+            "..#.....#...###..",
+            "..#.#.....#....##",
+        ]);
+        let order = field.vaporization_order(&Asteroid::new(8, 3));
+
+        assert_eq!(order.len(), field.asteroids.len() - 1);
+        assert_eq!(
+            order[..9].to_vec(),
+            vec![
+                Asteroid::new(8, 1),
+                Asteroid::new(9, 0),
+                Asteroid::new(9, 1),
+                Asteroid::new(10, 0),
+                Asteroid::new(9, 2),
+                Asteroid::new(11, 1),
+                Asteroid::new(12, 1),
+                Asteroid::new(11, 2),
+                Asteroid::new(15, 1),
+            ]
+        );
+        assert_eq!(order[order.len() - 1], Asteroid::new(14, 3));
+        assert!(field.vaporization_order(&Asteroid::new(1, 0)).len() == order.len());
+    }
+
     #[test]
     fn it_should_find_the_200_th_asteroid_to_be_destroyed() {
         let universe: Vec<&str> = vec![
@@ -357,7 +590,21 @@ mod tests {
         ];
         assert_eq!(
             find_monitoring_station_asteroid(&universe, Some(200)).1,
-            802.0
+            802
         );
     }
+
+    #[test]
+    #[should_panic(expected = "Asteroid not found")]
+    fn it_should_not_vaporize_a_zeroth_asteroid() {
+        let universe: Vec<&str> = vec![".#..#", ".....", "#####", "....#", "...##"];
+        find_monitoring_station_asteroid(&universe, Some(0));
+    }
+
+    #[test]
+    fn it_should_parse_vaporized_counts() {
+        assert_eq!(parse_count("12").unwrap(), 12);
+        assert!(parse_count("--color").is_err());
+        assert!(parse_count("-1").is_err());
+    }
 }
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::{collections::VecDeque, fs::read_to_string, io};
+use std::{collections::VecDeque, env, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let program: Vec<isize> = read_to_string("src/bin/day11-space-police/input.txt")?
@@ -8,105 +8,392 @@ fn main() -> io::Result<()> {
         .map(|line| line.parse::<isize>().unwrap())
         .collect();
 
-    let mut direction = Direction::Top;
-    let mut coordinates: (isize, isize) = (0, 0);
-    let mut panel: HashMap<(isize, isize), isize> = HashMap::new();
-    let mut computer = IntcodeComputer::new();
-    let mut has_painted = false;
-    computer.reset();
-    computer.push_input(get_color(&panel, &coordinates));
-    computer.push_instructions(program.clone());
-
-    loop {
-        match computer.run() {
-            ProgramState::Halted => break,
-            ProgramState::NeedInput => computer.push_input(get_color(&panel, &coordinates)),
-            ProgramState::Output(out) => {
-                match out {
-                    0 | 1 => (),
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("script") => {
+            let moves: Vec<(isize, isize)> = args[1..]
+                .iter()
+                .map(|step| {
+                    let step: Vec<isize> = step.split(',').map(|n| n.parse().unwrap()).collect();
+                    (step[0], step[1])
+                })
+                .collect();
+            let mut robot = Robot::new(ScriptedBrain::new(&moves), 0);
+            robot.run();
+            println!("{}", robot.hull.painted_panels());
+            println!("{}", robot.hull.render());
+        }
+        Some("langton") => {
+            let steps = args[2].parse::<usize>().unwrap();
+            let mut robot = Robot::new(RuleBrain::new(&args[1], steps), 0);
+            robot.run();
+            println!("{}", robot.hull.painted_panels());
+            println!("{}", robot.hull.render());
+        }
+        Some("random") => {
+            let seed = args[1].parse::<u64>().unwrap();
+            let steps = args[2].parse::<usize>().unwrap();
+            let mut robot = Robot::new(RandomBrain::new(seed, steps), 0);
+            robot.run();
+            println!("{}", robot.hull.painted_panels());
+            println!("{}", robot.hull.render());
+        }
+        Some("history") => {
+            let x = args[1].parse::<isize>().unwrap();
+            let y = args[2].parse::<isize>().unwrap();
+            let mut robot = Robot::new(IntcodeBrain::new(&program), 1);
+            robot.run();
+            println!("{:?}", robot.hull.history(&(x, y)));
+        }
+        _ => {
+            println!("{}", compute_part1_result(&program));
+            println!("{}", compute_part2_result(&program));
+        }
+    }
+
+    Ok(())
+}
+
+fn compute_part1_result(program: &[isize]) -> usize {
+    let mut robot = Robot::new(IntcodeBrain::new(program), 0);
+    robot.run();
+    robot.hull.painted_panels()
+}
+
+fn compute_part2_result(program: &[isize]) -> String {
+    let mut robot = Robot::new(IntcodeBrain::new(program), 1);
+    robot.run();
+    match read_letters(&robot.hull.pixels()) {
+        Some(letters) => letters,
+        None => robot.hull.render(),
+    }
+}
+
+trait Brain {
+    fn next(&mut self, color: isize) -> Option<(isize, isize)>;
+}
+
+struct IntcodeBrain {
+    computer: IntcodeComputer,
+}
+
+impl IntcodeBrain {
+    fn new(program: &[isize]) -> Self {
+        let mut computer = IntcodeComputer::new();
+        computer.reset();
+        computer.push_instructions(program.to_vec());
+        IntcodeBrain { computer }
+    }
+}
+
+impl Brain for IntcodeBrain {
+    fn next(&mut self, color: isize) -> Option<(isize, isize)> {
+        self.computer.push_input(color);
+        let mut outputs = vec![];
+        while outputs.len() < 2 {
+            match self.computer.run() {
+                ProgramState::Halted => return None,
+                ProgramState::NeedInput => panic!("Robot needs to move before reading again"),
+                ProgramState::Output(out) => match out {
+                    0 | 1 => outputs.push(out),
                     _ => panic!("Invalid output"),
-                }
-                if !has_painted {
-                    panel.insert(coordinates, out);
-                    has_painted = true;
-                } else {
-                    direction = get_new_direction(&direction, &out);
-                    coordinates = get_new_coordinates(&direction, &coordinates);
-                    has_painted = false;
-                }
+                },
             }
         }
+        Some((outputs[0], outputs[1]))
     }
+}
 
-    println!("{}", panel.keys().len());
-
-    direction = Direction::Top;
-    coordinates = (0, 0);
-    panel = HashMap::new();
-    let mut min_coordinates: (isize, isize) = (isize::max_value(), isize::max_value());
-    let mut max_coordinates: (isize, isize) = (isize::min_value(), isize::min_value());
-    computer.reset();
-    computer.push_input(1);
-    computer.push_instructions(program.clone());
-    loop {
-        match computer.run() {
-            ProgramState::Halted => break,
-            ProgramState::NeedInput => computer.push_input(get_color(&panel, &coordinates)),
-            ProgramState::Output(out) => {
-                match out {
-                    0 | 1 => (),
-                    _ => panic!("Invalid output"),
-                }
-                if !has_painted {
-                    panel.insert(coordinates, out);
-                    has_painted = true;
-                } else {
-                    direction = get_new_direction(&direction, &out);
-                    coordinates = get_new_coordinates(&direction, &coordinates);
-
-                    if min_coordinates.0 > coordinates.0 {
-                        min_coordinates.0 = coordinates.0;
-                    }
+struct ScriptedBrain {
+    moves: VecDeque<(isize, isize)>,
+}
 
-                    if min_coordinates.1 > coordinates.1 {
-                        min_coordinates.1 = coordinates.1;
-                    }
+impl ScriptedBrain {
+    fn new(moves: &[(isize, isize)]) -> Self {
+        ScriptedBrain {
+            moves: moves.iter().copied().collect(),
+        }
+    }
+}
 
-                    if max_coordinates.0 < coordinates.0 {
-                        max_coordinates.0 = coordinates.0;
-                    }
+impl Brain for ScriptedBrain {
+    fn next(&mut self, _color: isize) -> Option<(isize, isize)> {
+        self.moves.pop_front()
+    }
+}
 
-                    if max_coordinates.1 < coordinates.1 {
-                        max_coordinates.1 = coordinates.1;
-                    }
-                    has_painted = false;
-                }
+struct RuleBrain {
+    turns: Vec<isize>,
+    steps: usize,
+}
+
+impl RuleBrain {
+    fn new(rule: &str, steps: usize) -> Self {
+        RuleBrain {
+            turns: rule
+                .chars()
+                .map(|c| match c {
+                    'L' => 0,
+                    'R' => 1,
+                    n => panic!("Unknown turn {}", n),
+                })
+                .collect(),
+            steps,
+        }
+    }
+}
+
+impl Brain for RuleBrain {
+    fn next(&mut self, color: isize) -> Option<(isize, isize)> {
+        if self.steps == 0 || self.turns.is_empty() {
+            return None;
+        }
+        self.steps -= 1;
+
+        let color = color as usize % self.turns.len();
+        let paint = (color + 1) % self.turns.len();
+        Some((paint as isize, self.turns[color]))
+    }
+}
+
+struct RandomBrain {
+    state: u64,
+    steps: usize,
+}
+
+impl RandomBrain {
+    fn new(seed: u64, steps: usize) -> Self {
+        RandomBrain {
+            state: seed.max(1),
+            steps,
+        }
+    }
+
+    fn next_bit(&mut self) -> isize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 32) as isize & 1
+    }
+}
+
+impl Brain for RandomBrain {
+    fn next(&mut self, _color: isize) -> Option<(isize, isize)> {
+        if self.steps == 0 {
+            return None;
+        }
+        self.steps -= 1;
+
+        Some((self.next_bit(), self.next_bit()))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Hull {
+    panels: HashMap<(isize, isize), isize>,
+    history: HashMap<(isize, isize), Vec<isize>>,
+}
+
+impl Hull {
+    fn get_color(&self, coordinates: &(isize, isize)) -> isize {
+        get_color(&self.panels, coordinates)
+    }
+
+    fn paint(&mut self, coordinates: (isize, isize), color: isize) {
+        self.panels.insert(coordinates, color);
+        self.history.entry(coordinates).or_default().push(color);
+    }
+
+    fn painted_panels(&self) -> usize {
+        self.history.len()
+    }
+
+    fn history(&self, coordinates: &(isize, isize)) -> &[isize] {
+        match self.history.get(coordinates) {
+            Some(colors) => colors,
+            None => &[],
+        }
+    }
+
+    fn bounding_box(&self) -> Option<((isize, isize), (isize, isize))> {
+        let min_x = self.panels.keys().map(|c| c.0).min()?;
+        let max_x = self.panels.keys().map(|c| c.0).max()?;
+        let min_y = self.panels.keys().map(|c| c.1).min()?;
+        let max_y = self.panels.keys().map(|c| c.1).max()?;
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    fn colors(&self) -> Vec<Vec<isize>> {
+        let ((min_x, min_y), (max_x, max_y)) = match self.bounding_box() {
+            Some(bounding_box) => bounding_box,
+            None => return vec![],
+        };
+
+        (min_y..=max_y)
+            .rev()
+            .map(|y| (min_x..=max_x).map(|x| self.get_color(&(x, y))).collect())
+            .collect()
+    }
+
+    fn pixels(&self) -> Vec<Vec<bool>> {
+        self.colors()
+            .iter()
+            .map(|line| line.iter().map(|&color| color == 1).collect())
+            .collect()
+    }
+
+    fn render(&self) -> String {
+        self.colors()
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|&color| match color {
+                        0 => ' ',
+                        1 => '#',
+                        _ => std::char::from_digit(color as u32, 36).unwrap_or('?'),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+struct Robot<B: Brain> {
+    brain: B,
+    hull: Hull,
+    direction: Direction,
+    coordinates: (isize, isize),
+    steps: usize,
+}
+
+impl<B: Brain> Robot<B> {
+    fn new(brain: B, starting_color: isize) -> Self {
+        let mut hull = Hull::default();
+        if starting_color != 0 {
+            hull.panels.insert((0, 0), starting_color);
+        }
+        Robot {
+            brain,
+            hull,
+            direction: Direction::Top,
+            coordinates: (0, 0),
+            steps: 0,
+        }
+    }
+
+    fn step(&mut self) -> bool {
+        let color = self.hull.get_color(&self.coordinates);
+        match self.brain.next(color) {
+            Some((paint, turn)) => {
+                self.hull.paint(self.coordinates, paint);
+                self.direction = get_new_direction(&self.direction, &turn);
+                self.coordinates = get_new_coordinates(&self.direction, &self.coordinates);
+                self.steps += 1;
+                true
             }
+            None => false,
         }
     }
 
-    let mut image = vec![];
-    for y in min_coordinates.1..(max_coordinates.1 + 1) {
-        let mut line = vec![];
-        for x in (min_coordinates.0 + 1)..(max_coordinates.0) {
-            line.push(match get_color(&panel, &(x, y)) {
-                0 => " ",
-                1 => "#",
-                n => panic!("Unknown color {}", n),
-            });
+    fn run(&mut self) {
+        while self.step() {}
+    }
+}
+
+const GLYPHS: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn read_letters(pixels: &[Vec<bool>]) -> Option<String> {
+    let rows: Vec<&Vec<bool>> = pixels.iter().filter(|row| row.contains(&true)).collect();
+    if rows.len() != 6 {
+        return None;
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_ink = |x: usize| rows.iter().any(|row| row.get(x) == Some(&true));
+
+    let mut letters = String::new();
+    let mut x = 0;
+    while x < width {
+        if !is_ink(x) {
+            x += 1;
+            continue;
         }
-        image.push(line.join(""));
+
+        let start = x;
+        while x < width && is_ink(x) {
+            x += 1;
+        }
+
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (start..x)
+                    .map(|col| {
+                        if row.get(col) == Some(&true) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        letters.push(recognize_glyph(&glyph)?);
     }
 
-    image.reverse();
-    println!("{}", image.join("\n"));
+    if letters.is_empty() {
+        return None;
+    }
 
-    Ok(())
+    Some(letters)
+}
+
+fn recognize_glyph(glyph: &[String]) -> Option<char> {
+    GLYPHS.iter().find_map(|(letter, pattern)| {
+        let left = (0..pattern[0].len())
+            .find(|&col| pattern.iter().any(|line| &line[col..=col] == "#"))
+            .unwrap_or(0);
+        let right = (0..pattern[0].len())
+            .rev()
+            .find(|&col| pattern.iter().any(|line| &line[col..=col] == "#"))
+            .unwrap_or(0);
+
+        if pattern
+            .iter()
+            .zip(glyph.iter())
+            .all(|(line, row)| line[left..=right] == row[..])
+        {
+            Some(*letter)
+        } else {
+            None
+        }
+    })
 }
 
 fn get_color(panel: &HashMap<(isize, isize), isize>, coordinates: &(isize, isize)) -> isize {
     match panel.get(coordinates) {
-        Some(color) => color.clone(),
+        Some(color) => *color,
         _ => 0,
     }
 }
@@ -145,6 +432,7 @@ fn get_new_direction(direction: &Direction, output: &isize) -> Direction {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Direction {
     Top,
     Right,
@@ -183,8 +471,8 @@ impl IntcodeComputer {
     }
 
     fn push_instructions(&mut self, program: Vec<isize>) {
-        for ind in 0..program.len() {
-            self.instructions.insert(ind as isize, program[ind]);
+        for (ind, value) in program.into_iter().enumerate() {
+            self.instructions.insert(ind as isize, value);
         }
     }
 
@@ -289,13 +577,13 @@ impl IntcodeComputer {
 
     fn get_paramter(&self, mode: &isize, ind: &isize) -> isize {
         match mode {
-            0 => match self.instructions.get(&ind) {
-                Some(x) => x.clone(),
+            0 => match self.instructions.get(ind) {
+                Some(x) => *x,
                 _ => 0,
             },
-            1 => ind.clone(),
-            2 => match self.instructions.get(&ind) {
-                Some(x) => (self.relative_base + x.clone()),
+            1 => *ind,
+            2 => match self.instructions.get(ind) {
+                Some(x) => self.relative_base + *x,
                 _ => self.relative_base,
             },
             n => panic!("Unknown mode {}", n),
@@ -304,8 +592,196 @@ impl IntcodeComputer {
 
     fn get_value(&self, param: &isize) -> isize {
         match self.instructions.get(param) {
-            Some(x) => x.clone(),
+            Some(x) => *x,
             _ => 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::compute_part1_result;
+    use super::compute_part2_result;
+    use super::get_color;
+    use super::get_new_coordinates;
+    use super::get_new_direction;
+    use super::read_letters;
+    use super::Direction;
+    use super::Hull;
+    use super::RandomBrain;
+    use super::Robot;
+    use super::RuleBrain;
+    use super::ScriptedBrain;
+    use std::collections::HashMap;
+
+    const EXAMPLE: [(isize, isize); 7] = [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+
+    #[test]
+    fn it_should_get_the_color_of_a_panel() {
+        let mut panel: HashMap<(isize, isize), isize> = HashMap::new();
+        panel.insert((1, -2), 1);
+        panel.insert((0, 0), 0);
+
+        assert_eq!(get_color(&panel, &(1, -2)), 1);
+        assert_eq!(get_color(&panel, &(0, 0)), 0);
+        assert_eq!(get_color(&panel, &(7, 7)), 0);
+    }
+
+    #[test]
+    fn it_should_turn_left_and_right() {
+        let directions = [
+            Direction::Top,
+            Direction::Right,
+            Direction::Bottom,
+            Direction::Left,
+        ];
+        for (ind, direction) in directions.iter().enumerate() {
+            assert_eq!(get_new_direction(direction, &1), directions[(ind + 1) % 4]);
+            assert_eq!(get_new_direction(direction, &0), directions[(ind + 3) % 4]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown output 2")]
+    fn it_should_reject_unknown_turns() {
+        get_new_direction(&Direction::Top, &2);
+    }
+
+    #[test]
+    fn it_should_move_forward() {
+        assert_eq!(get_new_coordinates(&Direction::Top, &(2, 3)), (2, 4));
+        assert_eq!(get_new_coordinates(&Direction::Right, &(2, 3)), (3, 3));
+        assert_eq!(get_new_coordinates(&Direction::Bottom, &(2, 3)), (2, 2));
+        assert_eq!(get_new_coordinates(&Direction::Left, &(2, 3)), (1, 3));
+    }
+
+    #[test]
+    fn it_should_walk_like_langtons_ant() {
+        let mut robot = Robot::new(RuleBrain::new("RL", 5), 0);
+        for _ in 0..4 {
+            robot.step();
+        }
+
+        assert_eq!(robot.coordinates, (0, 0));
+        assert_eq!(robot.direction, Direction::Top);
+        assert_eq!(robot.hull.render(), "##\n##");
+
+        robot.run();
+
+        assert_eq!(robot.steps, 5);
+        assert_eq!(robot.coordinates, (-1, 0));
+        assert_eq!(robot.direction, Direction::Left);
+        assert_eq!(robot.hull.history(&(0, 0)), &[1, 0]);
+        assert_eq!(robot.hull.render(), " #\n##");
+    }
+
+    #[test]
+    fn it_should_cycle_through_multi_color_rules() {
+        let mut robot = Robot::new(RuleBrain::new("RLR", 3), 0);
+        robot.run();
+
+        assert_eq!(robot.hull.history(&(0, 0)), &[1]);
+        assert_eq!(robot.hull.get_color(&(1, 0)), 1);
+
+        let mut robot = Robot::new(RuleBrain::new("RLR", 5), 0);
+        robot.run();
+
+        assert_eq!(robot.hull.render(), "2#\n##");
+    }
+
+    #[test]
+    fn it_should_render_colors_beyond_black_and_white() {
+        let mut hull = Hull::default();
+        for (x, &color) in [0, 1, 2, 9, 12, -1].iter().enumerate() {
+            hull.paint((x as isize, 0), color);
+        }
+
+        assert_eq!(hull.render(), " #29c?");
+        assert_eq!(
+            hull.pixels(),
+            vec![vec![false, true, false, false, false, false]]
+        );
+    }
+
+    #[test]
+    fn it_should_replay_random_brains_from_a_seed() {
+        let mut robot = Robot::new(RandomBrain::new(42, 500), 0);
+        let mut same_seed = Robot::new(RandomBrain::new(42, 500), 0);
+        let mut other_seed = Robot::new(RandomBrain::new(7, 500), 0);
+        robot.run();
+        same_seed.run();
+        other_seed.run();
+
+        assert_eq!(robot.steps, 500);
+        assert_eq!(robot.hull.render(), same_seed.hull.render());
+        assert_eq!(robot.coordinates, same_seed.coordinates);
+        assert_ne!(robot.hull.render(), other_seed.hull.render());
+    }
+
+    #[test]
+    fn it_should_paint_the_hull_step_by_step() {
+        let mut robot = Robot::new(ScriptedBrain::new(&EXAMPLE), 0);
+
+        assert!(robot.step());
+        assert_eq!(robot.hull.get_color(&(0, 0)), 1);
+        assert_eq!(robot.coordinates, (-1, 0));
+        assert_eq!(robot.direction, Direction::Left);
+
+        robot.run();
+
+        assert_eq!(robot.steps, 7);
+        assert_eq!(robot.coordinates, (0, 1));
+        assert_eq!(robot.direction, Direction::Left);
+        assert_eq!(robot.hull.painted_panels(), 6);
+        assert_eq!(robot.hull.history(&(0, 0)), &[1, 0]);
+        assert_eq!(robot.hull.history(&(5, 5)), &[] as &[isize]);
+        assert!(!robot.step());
+    }
+
+    #[test]
+    fn it_should_render_the_whole_bounding_box() {
+        let mut robot = Robot::new(ScriptedBrain::new(&EXAMPLE), 0);
+        robot.run();
+
+        assert_eq!(robot.hull.bounding_box(), Some(((-1, -1), (1, 1))));
+        assert_eq!(robot.hull.render(), "  #\n  #\n## ");
+    }
+
+    #[test]
+    fn it_should_read_letters_painted_on_the_hull() {
+        let drawing = [
+            "#  #  ##  ",
+            "#  # #  # ",
+            "#### #  # ",
+            "#  # #### ",
+            "#  # #  # ",
+            "#  # #  # ",
+        ];
+        let mut hull = Hull::default();
+        for (y, line) in drawing.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                hull.paint(
+                    (x as isize + 3, -(y as isize)),
+                    if c == '#' { 1 } else { 0 },
+                );
+            }
+        }
+        hull.paint((-4, 2), 0);
+
+        assert_eq!(hull.pixels().len(), 8);
+        assert_eq!(read_letters(&hull.pixels()), Some(String::from("HA")));
+    }
+
+    #[test]
+    fn it_should_paint_the_registration_identifier() {
+        let program: Vec<isize> = std::fs::read_to_string("src/bin/day11-space-police/input.txt")
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|line| line.parse::<isize>().unwrap())
+            .collect();
+
+        assert_eq!(compute_part1_result(&program), 2219);
+        assert_eq!(compute_part2_result(&program), "HAFULAPE");
+    }
+}
@@ -1,47 +1,135 @@
+mod number_theory;
+
+use number_theory::{crt, lcm_all, NumberTheoryError};
 use regex::Regex;
-use std::{fs::read_to_string, io};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+use std::{env, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let file = read_to_string("src/bin/day12-the-n-body-problem/input.txt")?;
     let input: Vec<Planet> = file
         .trim()
         .split('\n')
-        .map(|l| Planet::new(parse_input_line(l), None))
+        .map(|l| Planet::new(&parse_input_line(l), None))
         .collect();
 
-    println!("{}", part1(&mut input.clone(), &1000));
-    println!("{}", part2(&mut input.clone()));
+    match env::args().nth(1).as_deref() {
+        Some("state") => {
+            let step = env::args().nth(2).map_or(0, |n| n.parse().unwrap());
+            let system = NBody::new(input);
+            for planet in system.state_at(step).unwrap_or_default() {
+                println!(
+                    "pos={:?} vel={:?} pot={} kin={} total={}",
+                    planet.position,
+                    planet.velocity,
+                    planet.potential_energy(),
+                    planet.kinetic_energy(),
+                    planet.total_energy()
+                );
+            }
+        }
+        Some("history") => {
+            let steps = env::args().nth(2).map_or(10, |n| n.parse().unwrap());
+            let mut system = NBody::new(input).with_history();
+            system.run(steps);
+            for (step, planets) in system.history().iter().enumerate() {
+                let energy: isize = planets.iter().map(|planet| planet.total_energy()).sum();
+                println!("{} {}", step, energy);
+            }
+        }
+        Some("crt") => {
+            let congruences: Vec<(i128, i128)> = env::args()
+                .skip(2)
+                .map(|congruence| {
+                    let mut parts = congruence.splitn(2, ':').map(|n| n.parse().unwrap());
+                    (parts.next().unwrap(), parts.next().unwrap())
+                })
+                .collect();
+            let (residue, modulus) = crt(&congruences).map_err(invalid_data)?;
+            println!("{} (mod {})", residue, modulus);
+        }
+        Some("bench") => {
+            let start = Instant::now();
+            let full_steps = part2_by_full_steps(&mut input.clone()).map_err(invalid_data)?;
+            let full_steps_time = start.elapsed();
+
+            let start = Instant::now();
+            let per_axis = part2(&input).map_err(invalid_data)?;
+            let per_axis_time = start.elapsed();
+
+            println!("full steps: {} in {:?}", full_steps, full_steps_time);
+            println!("per axis:   {} in {:?}", per_axis, per_axis_time);
+            println!(
+                "speedup:    {:.1}x",
+                full_steps_time.as_secs_f64() / per_axis_time.as_secs_f64()
+            );
+        }
+        _ => {
+            println!("{}", part1(&mut input.clone(), 1000));
+            println!("{}", part2(&input).map_err(invalid_data)?);
+        }
+    }
 
     Ok(())
 }
 
-fn part1(planets: &mut Vec<Planet>, steps: &usize) -> isize {
-    run_steps(planets, steps);
-    let mut energy = 0;
-    for planet in planets.iter() {
-        energy += (planet.position.0.abs() + planet.position.1.abs() + planet.position.2.abs())
-            * (planet.velocity.0.abs() + planet.velocity.1.abs() + planet.velocity.2.abs())
-    }
+fn invalid_data(error: NumberTheoryError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
 
-    energy
+fn part1(planets: &mut [Planet], steps: usize) -> isize {
+    let mut system = NBody::new(planets.to_vec());
+    system.run(steps);
+    planets.clone_from_slice(&system.planets);
+    system.total_energy()
+}
+
+fn part2(planets: &[Planet]) -> Result<isize, NumberTheoryError> {
+    let dimensions = planets.first().map_or(0, |planet| planet.dimensions());
+    let cycles: Vec<Cycle> = (0..dimensions)
+        .map(|axis| {
+            let state = AxisState::new(planets, axis);
+            detect_cycle(&state, |state: &mut AxisState| state.step())
+        })
+        .collect();
+
+    let start = cycles.iter().map(|cycle| cycle.start).max().unwrap_or(0);
+    let periods: Vec<usize> = cycles.iter().map(|cycle| cycle.period).collect();
+    combine_periods(start, &periods)
+}
+
+fn combine_periods(start: usize, periods: &[usize]) -> Result<isize, NumberTheoryError> {
+    if periods.is_empty() {
+        return Ok(0);
+    }
+    let periods: Vec<i128> = periods.iter().map(|&period| period as i128).collect();
+    lcm_all(&periods)?
+        .checked_add(start as i128)
+        .and_then(|steps| isize::try_from(steps).ok())
+        .ok_or(NumberTheoryError::Overflow)
 }
 
-fn part2(planets: &mut Vec<Planet>) -> isize {
-    let origin: Vec<Planet> = planets.clone();
+fn part2_by_full_steps(planets: &mut [Planet]) -> Result<isize, NumberTheoryError> {
+    let origin: Vec<Planet> = planets.to_vec();
+    let dimensions = origin.first().map_or(0, |planet| planet.dimensions());
     let mut steps = 0;
-    let mut axis_steps = vec![0, 0, 0];
+    let mut axis_steps = vec![0; dimensions];
 
     loop {
-        run_steps(planets, &1);
+        run_steps(planets, 1);
         steps += 1;
 
-        for i in 0..axis_steps.len() {
-            if axis_steps[i] != 0 {
+        for (axis, axis_step) in axis_steps.iter_mut().enumerate() {
+            if *axis_step != 0 {
                 continue;
             }
 
-            if same_on_axis(&i, &origin, planets) {
-                axis_steps[i] = steps;
+            if same_on_axis(axis, &origin, planets) {
+                *axis_step = steps;
             }
         }
 
@@ -50,189 +138,279 @@ fn part2(planets: &mut Vec<Planet>) -> isize {
         }
     }
 
-    least_common_multiple(axis_steps)
+    combine_periods(0, &axis_steps)
 }
 
-fn same_on_axis(axis: &usize, origin: &Vec<Planet>, planets: &Vec<Planet>) -> bool {
-    match axis {
-        0 => {
-            for i in 0..origin.len() {
-                if (origin[i].position.0 != planets[i].position.0)
-                    || (origin[i].velocity.0 != planets[i].velocity.0)
-                {
-                    return false;
-                }
-            }
-            true
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct AxisState {
+    positions: Vec<isize>,
+    velocities: Vec<isize>,
+}
+
+impl AxisState {
+    fn new(planets: &[Planet], axis: usize) -> Self {
+        AxisState {
+            positions: planets.iter().map(|planet| planet.position[axis]).collect(),
+            velocities: planets.iter().map(|planet| planet.velocity[axis]).collect(),
         }
-        1 => {
-            for i in 0..origin.len() {
-                if (origin[i].position.1 != planets[i].position.1)
-                    || (origin[i].velocity.1 != planets[i].velocity.1)
-                {
-                    return false;
-                }
-            }
-            true
+    }
+
+    fn step(&mut self) {
+        let positions = &self.positions;
+        for (velocity, position) in self.velocities.iter_mut().zip(positions.iter()) {
+            *velocity += positions
+                .iter()
+                .map(|other| (other - position).signum())
+                .sum::<isize>();
+        }
+        for (position, velocity) in self.positions.iter_mut().zip(self.velocities.iter()) {
+            *position += velocity;
         }
-        2 => {
-            for i in 0..origin.len() {
-                if (origin[i].position.2 != planets[i].position.2)
-                    || (origin[i].velocity.2 != planets[i].velocity.2)
-                {
-                    return false;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cycle {
+    start: usize,
+    period: usize,
+}
+
+fn fingerprint<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn detect_cycle<S: Clone + Eq + Hash>(initial: &S, step: impl Fn(&mut S)) -> Cycle {
+    let state_at = |steps: usize| {
+        let mut state = initial.clone();
+        for _ in 0..steps {
+            step(&mut state);
+        }
+        state
+    };
+
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+    let mut state = initial.clone();
+    let mut steps = 0;
+
+    loop {
+        let mut key = fingerprint(&state);
+        loop {
+            match seen.entry(key) {
+                Entry::Occupied(entry) if state_at(*entry.get()) == state => {
+                    let start = *entry.get();
+                    return Cycle {
+                        start,
+                        period: steps - start,
+                    };
+                }
+                Entry::Occupied(_) => key = key.wrapping_add(1),
+                Entry::Vacant(entry) => {
+                    entry.insert(steps);
+                    break;
                 }
             }
-            true
         }
-        n => panic!("Unknown axis {}", n),
+        step(&mut state);
+        steps += 1;
     }
 }
 
-fn least_common_multiple(numbers: Vec<isize>) -> isize {
-    if numbers.len() == 0 {
-        return 0;
-    }
-    let mut r_val = numbers[0];
-    for i in 0..numbers.len() {
-        r_val = (r_val * numbers[i]) / greatest_common_divisor(r_val, numbers[i]);
-    }
-    r_val
+fn same_on_axis(axis: usize, origin: &[Planet], planets: &[Planet]) -> bool {
+    origin
+        .iter()
+        .zip(planets.iter())
+        .all(|(a, b)| a.position[axis] == b.position[axis] && a.velocity[axis] == b.velocity[axis])
+}
+
+fn parse_input_line(line: &str) -> Vec<isize> {
+    let re = Regex::new(r"\w+=(-?\d+)").unwrap();
+    re.captures_iter(line)
+        .map(|captures| captures[1].parse::<isize>().unwrap())
+        .collect()
 }
 
-fn greatest_common_divisor(a: isize, b: isize) -> isize {
-    if b == 0 {
-        return a;
+fn run_steps(planets: &mut [Planet], steps: usize) {
+    for _ in 0..steps {
+        let planets_copy = planets.to_vec();
+        for planet in planets.iter_mut() {
+            planet.update_velocity(&planets_copy).update_position();
+        }
     }
-    greatest_common_divisor(b, a % b)
 }
 
-fn parse_input_line(line: &str) -> (isize, isize, isize) {
-    let re = Regex::new(r"<x=(-?\d+),\sy=(-?\d+),\sz=(-?\d+)>").unwrap();
-    let captures = re.captures_iter(line).next().unwrap();
-    (
-        captures[1].parse::<isize>().unwrap(),
-        captures[2].parse::<isize>().unwrap(),
-        captures[3].parse::<isize>().unwrap(),
-    )
+#[derive(Clone, Debug, PartialEq)]
+struct NBody {
+    planets: Vec<Planet>,
+    step: usize,
+    history: Option<Vec<Vec<Planet>>>,
 }
 
-fn run_steps(planets: &mut Vec<Planet>, steps: &usize) -> () {
-    for _ in 0..steps.clone() {
-        let planets_copy = planets.clone();
-        for planet in planets.iter_mut() {
-            let other_planets: Vec<&Planet> =
-                planets_copy.iter().filter(|plt| !planet.eq(plt)).collect();
-            planet.update_velocity(&other_planets).update_position();
+impl NBody {
+    fn new(planets: Vec<Planet>) -> Self {
+        NBody {
+            planets,
+            step: 0,
+            history: None,
         }
     }
+
+    fn with_history(mut self) -> Self {
+        self.history = Some(vec![self.planets.clone()]);
+        self
+    }
+
+    fn step(&mut self) {
+        run_steps(&mut self.planets, 1);
+        self.step += 1;
+        if let Some(history) = self.history.as_mut() {
+            history.push(self.planets.clone());
+        }
+    }
+
+    fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    fn state_at(&self, step: usize) -> Option<Vec<Planet>> {
+        if step >= self.step {
+            let mut planets = self.planets.clone();
+            run_steps(&mut planets, step - self.step);
+            return Some(planets);
+        }
+
+        self.history
+            .as_ref()
+            .and_then(|history| history.get(step))
+            .cloned()
+    }
+
+    fn history(&self) -> &[Vec<Planet>] {
+        match self.history.as_ref() {
+            Some(history) => history,
+            None => &[],
+        }
+    }
+
+    fn total_energy(&self) -> isize {
+        self.planets
+            .iter()
+            .map(|planet| planet.total_energy())
+            .sum()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 struct Planet {
-    position: (isize, isize, isize),
-    velocity: (isize, isize, isize),
-    other_planets: Vec<Planet>,
+    position: Vec<isize>,
+    velocity: Vec<isize>,
 }
 
 impl Planet {
-    fn new(pos: (isize, isize, isize), vel: Option<(isize, isize, isize)>) -> Self {
+    fn new(pos: &[isize], vel: Option<&[isize]>) -> Self {
         Planet {
-            position: pos,
+            position: pos.to_vec(),
             velocity: match vel {
-                Some(v) => v,
-                _ => (0, 0, 0),
+                Some(v) => v.to_vec(),
+                _ => vec![0; pos.len()],
             },
-            other_planets: vec![],
         }
     }
 
-    fn update_velocity(&mut self, other_planets: &Vec<&Planet>) -> &mut Self {
-        let mut gravity = (0, 0, 0);
-        for planet in other_planets.iter() {
-            if planet.position.0 > self.position.0 {
-                gravity.0 += 1;
-            } else if planet.position.0 < self.position.0 {
-                gravity.0 -= 1;
-            }
-
-            if planet.position.1 > self.position.1 {
-                gravity.1 += 1;
-            } else if planet.position.1 < self.position.1 {
-                gravity.1 -= 1;
-            }
+    fn dimensions(&self) -> usize {
+        self.position.len()
+    }
 
-            if planet.position.2 > self.position.2 {
-                gravity.2 += 1;
-            } else if planet.position.2 < self.position.2 {
-                gravity.2 -= 1;
+    fn update_velocity(&mut self, other_planets: &[Planet]) -> &mut Self {
+        for planet in other_planets.iter() {
+            for axis in 0..self.dimensions() {
+                self.velocity[axis] += (planet.position[axis] - self.position[axis]).signum();
             }
         }
 
-        self.velocity.0 += gravity.0;
-        self.velocity.1 += gravity.1;
-        self.velocity.2 += gravity.2;
-
         self
     }
 
     fn update_position(&mut self) -> &mut Self {
-        self.position.0 += self.velocity.0;
-        self.position.1 += self.velocity.1;
-        self.position.2 += self.velocity.2;
+        for (position, velocity) in self.position.iter_mut().zip(self.velocity.iter()) {
+            *position += velocity;
+        }
         self
     }
+
+    fn potential_energy(&self) -> isize {
+        self.position.iter().map(|p| p.abs()).sum()
+    }
+
+    fn kinetic_energy(&self) -> isize {
+        self.velocity.iter().map(|v| v.abs()).sum()
+    }
+
+    fn total_energy(&self) -> isize {
+        self.potential_energy() * self.kinetic_energy()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::combine_periods;
+    use super::detect_cycle;
     use super::parse_input_line;
     use super::part1;
     use super::part2;
+    use super::part2_by_full_steps;
     use super::run_steps;
+    use super::AxisState;
+    use super::Cycle;
+    use super::NBody;
+    use super::NumberTheoryError;
     use super::Planet;
+    use std::fs::read_to_string;
 
     #[test]
     fn it_should_parse_input_lines() {
-        assert_eq!(parse_input_line(&"<x=-1, y=0, z=2>"), (-1, 0, 2));
-        assert_eq!(parse_input_line(&"<x=2, y=-10, z=-7>"), (2, -10, -7));
-        assert_eq!(parse_input_line(&"<x=4, y=-8, z=8>"), (4, -8, 8));
-        assert_eq!(parse_input_line(&"<x=3, y=5, z=-1>"), (3, 5, -1));
+        assert_eq!(parse_input_line("<x=-1, y=0, z=2>"), vec![-1, 0, 2]);
+        assert_eq!(parse_input_line("<x=2, y=-10, z=-7>"), vec![2, -10, -7]);
+        assert_eq!(parse_input_line("<x=4, y=-8, z=8>"), vec![4, -8, 8]);
+        assert_eq!(parse_input_line("<x=3, y=5, z=-1>"), vec![3, 5, -1]);
     }
 
     #[test]
     fn it_should_update_velocity_and_position() {
-        let mut planet = Planet::new((-1, 0, 2), None);
+        let mut planet = Planet::new(&[-1, 0, 2], None);
 
-        let planet2 = Planet::new((2, -10, -7), None);
-        let planet3 = Planet::new((4, -8, 8), None);
-        let planet4 = Planet::new((3, 5, -1), None);
+        let planet2 = Planet::new(&[2, -10, -7], None);
+        let planet3 = Planet::new(&[4, -8, 8], None);
+        let planet4 = Planet::new(&[3, 5, -1], None);
 
-        let other_planets: Vec<&Planet> = vec![&planet2, &planet3, &planet4];
+        let other_planets: Vec<Planet> = vec![planet2, planet3, planet4];
 
         planet.update_velocity(&other_planets).update_position();
 
-        assert_eq!(planet, Planet::new((2, -1, 1), Some((3, -1, -1))));
+        assert_eq!(planet, Planet::new(&[2, -1, 1], Some(&[3, -1, -1])));
     }
 
     #[test]
     fn it_should_run_steps_1() {
         let mut planets = vec![
-            Planet::new((-1, 0, 2), None),
-            Planet::new((2, -10, -7), None),
-            Planet::new((4, -8, 8), None),
-            Planet::new((3, 5, -1), None),
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
         ];
 
-        run_steps(&mut planets, &1);
+        run_steps(&mut planets, 1);
 
         assert_eq!(
             planets,
             vec![
-                Planet::new((2, -1, 1), Some((3, -1, -1))),
-                Planet::new((3, -7, -4), Some((1, 3, 3))),
-                Planet::new((1, -7, 5), Some((-3, 1, -3))),
-                Planet::new((2, 2, 0), Some((-1, -3, 1))),
+                Planet::new(&[2, -1, 1], Some(&[3, -1, -1])),
+                Planet::new(&[3, -7, -4], Some(&[1, 3, 3])),
+                Planet::new(&[1, -7, 5], Some(&[-3, 1, -3])),
+                Planet::new(&[2, 2, 0], Some(&[-1, -3, 1])),
             ]
         );
     }
@@ -240,21 +418,21 @@ mod tests {
     #[test]
     fn it_should_run_steps_2() {
         let mut planets = vec![
-            Planet::new((-1, 0, 2), None),
-            Planet::new((2, -10, -7), None),
-            Planet::new((4, -8, 8), None),
-            Planet::new((3, 5, -1), None),
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
         ];
 
-        run_steps(&mut planets, &2);
+        run_steps(&mut planets, 2);
 
         assert_eq!(
             planets,
             vec![
-                Planet::new((5, -3, -1), Some((3, -2, -2))),
-                Planet::new((1, -2, 2), Some((-2, 5, 6))),
-                Planet::new((1, -4, -1), Some((0, 3, -6))),
-                Planet::new((1, -4, 2), Some((-1, -6, 2))),
+                Planet::new(&[5, -3, -1], Some(&[3, -2, -2])),
+                Planet::new(&[1, -2, 2], Some(&[-2, 5, 6])),
+                Planet::new(&[1, -4, -1], Some(&[0, 3, -6])),
+                Planet::new(&[1, -4, 2], Some(&[-1, -6, 2])),
             ]
         );
     }
@@ -262,21 +440,21 @@ mod tests {
     #[test]
     fn it_should_run_steps_10() {
         let mut planets = vec![
-            Planet::new((-1, 0, 2), None),
-            Planet::new((2, -10, -7), None),
-            Planet::new((4, -8, 8), None),
-            Planet::new((3, 5, -1), None),
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
         ];
 
-        run_steps(&mut planets, &10);
+        run_steps(&mut planets, 10);
 
         assert_eq!(
             planets,
             vec![
-                Planet::new((2, 1, -3), Some((-3, -2, 1))),
-                Planet::new((1, -8, 0), Some((-1, 1, 3))),
-                Planet::new((3, -6, 1), Some((3, 2, -3))),
-                Planet::new((2, 0, 4), Some((1, -1, -1))),
+                Planet::new(&[2, 1, -3], Some(&[-3, -2, 1])),
+                Planet::new(&[1, -8, 0], Some(&[-1, 1, 3])),
+                Planet::new(&[3, -6, 1], Some(&[3, 2, -3])),
+                Planet::new(&[2, 0, 4], Some(&[1, -1, -1])),
             ]
         );
     }
@@ -284,48 +462,215 @@ mod tests {
     #[test]
     fn it_should_compute_energy_1() {
         let mut planets = vec![
-            Planet::new((-1, 0, 2), None),
-            Planet::new((2, -10, -7), None),
-            Planet::new((4, -8, 8), None),
-            Planet::new((3, 5, -1), None),
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
         ];
 
-        assert_eq!(part1(&mut planets, &10), 179);
+        assert_eq!(part1(&mut planets, 10), 179);
     }
 
     #[test]
     fn it_should_compute_energy_2() {
         let mut planets = vec![
-            Planet::new((-8, -10, 0), None),
-            Planet::new((5, 5, 10), None),
-            Planet::new((2, -7, 3), None),
-            Planet::new((9, -8, -3), None),
+            Planet::new(&[-8, -10, 0], None),
+            Planet::new(&[5, 5, 10], None),
+            Planet::new(&[2, -7, 3], None),
+            Planet::new(&[9, -8, -3], None),
         ];
 
-        assert_eq!(part1(&mut planets, &100), 1940);
+        assert_eq!(part1(&mut planets, 100), 1940);
     }
 
     #[test]
     fn it_should_compute_the_number_of_steps_to_initial_state_1() {
         let mut planets = vec![
-            Planet::new((-1, 0, 2), None),
-            Planet::new((2, -10, -7), None),
-            Planet::new((4, -8, 8), None),
-            Planet::new((3, 5, -1), None),
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
         ];
 
-        assert_eq!(part2(&mut planets), 2772);
+        assert_eq!(part2(&planets), Ok(2772));
+        assert_eq!(part2_by_full_steps(&mut planets), Ok(2772));
     }
 
     #[test]
     fn it_should_compute_the_number_of_steps_to_initial_state_2() {
-        let mut planets = vec![
-            Planet::new((-8, -10, 0), None),
-            Planet::new((5, 5, 10), None),
-            Planet::new((2, -7, 3), None),
-            Planet::new((9, -8, -3), None),
+        let planets = vec![
+            Planet::new(&[-8, -10, 0], None),
+            Planet::new(&[5, 5, 10], None),
+            Planet::new(&[2, -7, 3], None),
+            Planet::new(&[9, -8, -3], None),
         ];
 
-        assert_eq!(part2(&mut planets), 4686774924);
+        assert_eq!(part2(&planets), Ok(4686774924));
+    }
+
+    #[test]
+    fn it_should_find_the_same_period_per_axis_as_with_full_steps() {
+        let file = read_to_string("src/bin/day12-the-n-body-problem/input.txt").unwrap();
+        let mut planets: Vec<Planet> = file
+            .trim()
+            .split('\n')
+            .map(|l| Planet::new(&parse_input_line(l), None))
+            .collect();
+
+        assert_eq!(part2(&planets), Ok(353620566035124));
+        assert_eq!(part2_by_full_steps(&mut planets), part2(&planets));
+    }
+
+    #[test]
+    fn it_should_simulate_any_number_of_bodies_and_dimensions() {
+        let mut system = NBody::new(vec![
+            Planet::new(&[0, 0, 0, 0], None),
+            Planet::new(&[3, -2, 1, 5], None),
+            Planet::new(&[-4, 2, 1, 0], None),
+            Planet::new(&[1, 1, 1, 1], None),
+            Planet::new(&[2, 7, -3, 1], None),
+        ]);
+        system.run(1);
+
+        assert_eq!(
+            system.planets[0],
+            Planet::new(&[2, 2, 2, 3], Some(&[2, 2, 2, 3]))
+        );
+        assert_eq!(system.planets[3].dimensions(), 4);
+        assert_eq!(
+            system.state_at(5),
+            NBody::new(system.state_at(3).unwrap()).state_at(2)
+        );
+
+        let mut flat = NBody::new(vec![Planet::new(&[0], None), Planet::new(&[4], None)]);
+        flat.run(2);
+        assert_eq!(
+            flat.planets,
+            vec![
+                Planet::new(&[3], Some(&[2])),
+                Planet::new(&[1], Some(&[-2]))
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_compute_energy_per_body() {
+        let planet = Planet::new(&[2, 1, -3], Some(&[-3, -2, 1]));
+
+        assert_eq!(planet.potential_energy(), 6);
+        assert_eq!(planet.kinetic_energy(), 6);
+        assert_eq!(planet.total_energy(), 36);
+    }
+
+    #[test]
+    fn it_should_keep_a_snapshot_history() {
+        let planets = vec![
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
+        ];
+        let mut system = NBody::new(planets.clone()).with_history();
+        system.run(10);
+
+        assert_eq!(system.history().len(), 11);
+        assert_eq!(system.history()[0], planets);
+        assert_eq!(system.state_at(0), Some(planets.clone()));
+        assert_eq!(
+            system.state_at(1).unwrap()[0],
+            Planet::new(&[2, -1, 1], Some(&[3, -1, -1]))
+        );
+        assert_eq!(system.total_energy(), 179);
+
+        let mut without_history = NBody::new(planets);
+        without_history.run(10);
+        assert_eq!(without_history.state_at(1), None);
+        assert_eq!(without_history.history().len(), 0);
+    }
+
+    #[test]
+    fn it_should_step_a_single_axis() {
+        let planets = vec![
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
+        ];
+        let mut state = AxisState::new(&planets, 1);
+        state.step();
+
+        assert_eq!(state.positions, vec![-1, -7, -7, 2]);
+        assert_eq!(state.velocities, vec![-1, 3, 1, -3]);
+    }
+
+    #[test]
+    fn it_should_detect_cycles_per_axis() {
+        let planets = vec![
+            Planet::new(&[-1, 0, 2], None),
+            Planet::new(&[2, -10, -7], None),
+            Planet::new(&[4, -8, 8], None),
+            Planet::new(&[3, 5, -1], None),
+        ];
+        let cycles: Vec<Cycle> = (0..3)
+            .map(|axis| {
+                detect_cycle(&AxisState::new(&planets, axis), |s: &mut AxisState| {
+                    s.step()
+                })
+            })
+            .collect();
+
+        assert_eq!(
+            cycles,
+            vec![
+                Cycle {
+                    start: 0,
+                    period: 18
+                },
+                Cycle {
+                    start: 0,
+                    period: 28
+                },
+                Cycle {
+                    start: 0,
+                    period: 44
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_detect_cycles_that_do_not_start_at_the_origin() {
+        assert_eq!(
+            detect_cycle(&3u64, |x: &mut u64| *x = (*x * *x + 1) % 10),
+            Cycle {
+                start: 1,
+                period: 6
+            }
+        );
+        assert_eq!(
+            detect_cycle(&(0u8, 0u8), |s: &mut (u8, u8)| *s =
+                ((s.0 + 1).min(4), (s.1 + 1) % 6)),
+            Cycle {
+                start: 4,
+                period: 6
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_fail_when_the_period_does_not_fit() {
+        assert_eq!(combine_periods(3, &[18, 28, 44]), Ok(2775));
+        assert_eq!(
+            combine_periods(0, &[(1 << 40) - 1, 1 << 40, (1 << 40) + 1]),
+            Err(NumberTheoryError::Overflow)
+        );
+    }
+
+    #[test]
+    fn it_should_not_find_a_period_without_planets() {
+        assert_eq!(combine_periods(0, &[]), Ok(0));
+        assert_eq!(combine_periods(3, &[]), Ok(0));
+        assert_eq!(part2(&[]), Ok(0));
+        assert_eq!(part2_by_full_steps(&mut []), Ok(0));
     }
 }
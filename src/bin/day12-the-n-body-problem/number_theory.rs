@@ -0,0 +1,154 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberTheoryError {
+    Overflow,
+    InvalidModulus(i128),
+    NoSolution,
+}
+
+impl fmt::Display for NumberTheoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumberTheoryError::Overflow => write!(f, "Result does not fit in the integer type"),
+            NumberTheoryError::InvalidModulus(modulus) => write!(f, "Invalid modulus {}", modulus),
+            NumberTheoryError::NoSolution => write!(f, "Congruences have no common solution"),
+        }
+    }
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let rest = a % b;
+        a = b;
+        b = rest;
+    }
+    a
+}
+
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (1, 0);
+    let (mut old_t, mut t) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+        (old_t, t) = (t, old_t - quotient * t);
+    }
+    if old_r < 0 {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+pub fn lcm(a: i128, b: i128) -> Result<i128, NumberTheoryError> {
+    if a == 0 || b == 0 {
+        return Ok(0);
+    }
+    (a / gcd(a, b))
+        .checked_mul(b)
+        .map(|multiple| multiple.abs())
+        .ok_or(NumberTheoryError::Overflow)
+}
+
+pub fn lcm_all(numbers: &[i128]) -> Result<i128, NumberTheoryError> {
+    numbers
+        .iter()
+        .try_fold(1, |multiple, &number| lcm(multiple, number))
+}
+
+pub fn crt(congruences: &[(i128, i128)]) -> Result<(i128, i128), NumberTheoryError> {
+    congruences.iter().try_fold(
+        (0, 1),
+        |(residue, modulus), &(other_residue, other_modulus)| {
+            if other_modulus <= 0 {
+                return Err(NumberTheoryError::InvalidModulus(other_modulus));
+            }
+
+            let (divisor, inverse, _) = extended_gcd(modulus, other_modulus);
+            let difference = other_residue.rem_euclid(other_modulus) - residue;
+            if difference % divisor != 0 {
+                return Err(NumberTheoryError::NoSolution);
+            }
+
+            let step = other_modulus / divisor;
+            let combined = lcm(modulus, other_modulus)?;
+            let factor = mul_mod(difference / divisor, inverse, step)?;
+            let offset = modulus
+                .checked_mul(factor)
+                .ok_or(NumberTheoryError::Overflow)?;
+            let residue = residue
+                .checked_add(offset)
+                .ok_or(NumberTheoryError::Overflow)?;
+            Ok((residue.rem_euclid(combined), combined))
+        },
+    )
+}
+
+fn mul_mod(a: i128, b: i128, modulus: i128) -> Result<i128, NumberTheoryError> {
+    let (a, b) = (a.rem_euclid(modulus), b.rem_euclid(modulus));
+    a.checked_mul(b)
+        .map(|product| product % modulus)
+        .ok_or(NumberTheoryError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::crt;
+    use super::extended_gcd;
+    use super::gcd;
+    use super::lcm;
+    use super::lcm_all;
+    use super::NumberTheoryError;
+
+    #[test]
+    fn it_should_compute_gcd() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(0, 0), 0);
+    }
+
+    #[test]
+    fn it_should_compute_extended_gcd() {
+        for &(a, b) in &[(240, 46), (46, 240), (-15, 35), (7, 0), (0, -9)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn it_should_compute_lcm_of_many_values() {
+        assert_eq!(lcm_all(&[18, 28, 44]), Ok(2772));
+        assert_eq!(lcm_all(&[2028, 5898, 4702]), Ok(4686774924));
+        assert_eq!(lcm_all(&[]), Ok(1));
+        assert_eq!(lcm(0, 5), Ok(0));
+    }
+
+    #[test]
+    fn it_should_report_overflow_instead_of_wrapping() {
+        let big = i128::MAX / 2 + 1;
+
+        assert_eq!(lcm(big, big - 1), Err(NumberTheoryError::Overflow));
+        assert_eq!(
+            lcm_all(&[1 << 100, (1 << 40) - 1, (1 << 40) + 1]),
+            Err(NumberTheoryError::Overflow)
+        );
+    }
+
+    #[test]
+    fn it_should_solve_congruences() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        assert_eq!(crt(&[(1, 4), (3, 6)]), Ok((9, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), Err(NumberTheoryError::NoSolution));
+        assert_eq!(
+            crt(&[(1, 4), (2, 0)]),
+            Err(NumberTheoryError::InvalidModulus(0))
+        );
+        assert_eq!(crt(&[]), Ok((0, 1)));
+    }
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
-use std::{collections::VecDeque, fs::read_to_string, io};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use std::{collections::VecDeque, env, fs, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let program: Vec<isize> = read_to_string("src/bin/day13-care-package/input.txt")?
@@ -8,80 +11,559 @@ fn main() -> io::Result<()> {
         .map(|line| line.parse::<isize>().unwrap())
         .collect();
 
-    let mut blocks: Vec<Vec<isize>> = vec![];
-    let mut block: Vec<isize> = vec![];
-    let mut computer = IntcodeComputer::new();
-    computer.reset();
-    computer.push_instructions(program.clone());
+    match env::args().nth(1).as_deref() {
+        Some("screen") => {
+            let mut arcade = Arcade::new(&program, false);
+            arcade.run();
+            println!("{}", arcade.screen().render());
+        }
+        Some("play") => {
+            let mut arcade = Arcade::new(&program, true);
+            let score = play(&mut arcade, io::stdin().lock(), io::stdout().lock())?;
+            println!("Final score: {}", score);
+            if let Some(path) = env::args().nth(2) {
+                arcade.recording().save(&path)?;
+            }
+        }
+        Some("record") => {
+            let path = env::args().nth(2).expect("usage: record <file>");
+            let report = Arcade::new(&program, true).run_with(FollowBall, MAX_FRAMES);
+            report.recording.save(&path)?;
+            println!("{}", report);
+        }
+        Some("replay") => {
+            let path = env::args().nth(2).expect("usage: replay <file>");
+            let recording = Recording::load(&path)?;
+            let mut arcade = Arcade::new(&program, true);
+            let report = arcade.run_with(Playback::new(&recording), MAX_FRAMES);
+            println!("{}", arcade.screen().render());
+            println!("{}", report);
+        }
+        Some("autopilot") => {
+            let mut arcade = Arcade::new(&program, true);
+            let report = arcade.run_with(&mut FollowBall, MAX_FRAMES);
+            println!("{}", arcade.screen().render());
+            println!("{}", report);
+        }
+        Some("compare") => {
+            let seed = env::args().nth(2).map_or(1, |n| n.parse().unwrap());
+            let follow = Arcade::new(&program, true).run_with(&mut FollowBall, MAX_FRAMES);
+            let strategies: Vec<(&str, Box<dyn Joystick>)> = vec![
+                ("follow-ball", Box::new(FollowBall)),
+                ("predictive", Box::new(Predictive::default())),
+                ("random", Box::new(RandomJoystick::new(seed))),
+                ("playback", Box::new(Playback::new(&follow.recording))),
+            ];
+            for (name, joystick) in strategies {
+                let report = Arcade::new(&program, true).run_with(joystick, MAX_FRAMES);
+                println!("{:<12} {}", name, report);
+            }
+        }
+        _ => {
+            println!("{}", compute_part1_result(&program));
+            println!("{}", compute_part2_result(&program));
+        }
+    }
 
-    loop {
-        if block.len() == 3 {
-            blocks.push(block);
-            block = vec![]
+    Ok(())
+}
+
+fn compute_part1_result(program: &[isize]) -> usize {
+    let mut arcade = Arcade::new(program, false);
+    arcade.run();
+    arcade.screen().count(Tile::Block)
+}
+
+fn compute_part2_result(program: &[isize]) -> isize {
+    Arcade::new(program, true)
+        .run_with(&mut FollowBall, MAX_FRAMES)
+        .score
+}
+
+const MAX_FRAMES: usize = 100_000;
+
+trait Joystick {
+    fn next(&mut self, screen: &Screen) -> Option<isize>;
+}
+
+impl<J: Joystick + ?Sized> Joystick for &mut J {
+    fn next(&mut self, screen: &Screen) -> Option<isize> {
+        (**self).next(screen)
+    }
+}
+
+impl<J: Joystick + ?Sized> Joystick for Box<J> {
+    fn next(&mut self, screen: &Screen) -> Option<isize> {
+        (**self).next(screen)
+    }
+}
+
+struct FollowBall;
+
+impl Joystick for FollowBall {
+    fn next(&mut self, screen: &Screen) -> Option<isize> {
+        match (screen.find(Tile::Ball), screen.find(Tile::Paddle)) {
+            (Some(ball), Some(paddle)) => Some((ball.0 - paddle.0).signum()),
+            _ => Some(0),
         }
+    }
+}
 
-        match computer.run() {
-            ProgramState::Halted => break,
-            ProgramState::NeedInput => panic!("No need for input ?"),
-            ProgramState::Output(out) => block.push(out),
+#[derive(Default)]
+struct Predictive {
+    previous_ball: Option<(isize, isize)>,
+}
+
+impl Predictive {
+    fn landing_x(
+        screen: &Screen,
+        ball: (isize, isize),
+        velocity: (isize, isize),
+        paddle_y: isize,
+    ) -> Option<isize> {
+        let (mut x, mut y) = ball;
+        let mut dx = velocity.0;
+        while y < paddle_y - 1 {
+            match screen.tile(x + dx, y) {
+                Tile::Wall => dx = -dx,
+                Tile::Block => return None,
+                _ => (),
+            }
+            if screen.tile(x + dx, y + 1) == Tile::Block || screen.tile(x, y + 1) == Tile::Block {
+                return None;
+            }
+            x += dx;
+            y += 1;
         }
+        Some(x)
     }
+}
 
-    println!(
-        "{}",
-        blocks
-            .iter()
-            .filter(|b| b[2] == 2)
-            .collect::<Vec<&Vec<isize>>>()
-            .len()
-    );
+impl Joystick for Predictive {
+    fn next(&mut self, screen: &Screen) -> Option<isize> {
+        let (ball, paddle) = match (screen.find(Tile::Ball), screen.find(Tile::Paddle)) {
+            (Some(ball), Some(paddle)) => (ball, paddle),
+            _ => return Some(0),
+        };
+
+        let target = match self.previous_ball {
+            Some(previous) if ball.1 > previous.1 => {
+                let velocity = (ball.0 - previous.0, ball.1 - previous.1);
+                Predictive::landing_x(screen, ball, velocity, paddle.1).unwrap_or(ball.0)
+            }
+            _ => ball.0,
+        };
+        self.previous_ball = Some(ball);
+
+        Some((target - paddle.0).signum())
+    }
+}
 
-    computer.reset();
-    let mut game = program.clone();
-    game[0] = 2;
-    computer.push_instructions(game);
+struct RandomJoystick {
+    state: u64,
+}
 
-    let mut ball: Option<isize> = None;
-    let mut joystick: Option<isize> = None;
-    let mut score = 0;
+impl RandomJoystick {
+    fn new(seed: u64) -> Self {
+        RandomJoystick { state: seed.max(1) }
+    }
+}
+
+impl Joystick for RandomJoystick {
+    fn next(&mut self, _screen: &Screen) -> Option<isize> {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        Some((self.state >> 32) as isize % 3 - 1)
+    }
+}
+
+struct Playback {
+    inputs: VecDeque<(usize, isize)>,
+    frame: usize,
+}
+
+impl Playback {
+    fn new(recording: &Recording) -> Self {
+        Playback {
+            inputs: recording.inputs.iter().copied().collect(),
+            frame: 0,
+        }
+    }
+}
+
+impl Joystick for Playback {
+    fn next(&mut self, _screen: &Screen) -> Option<isize> {
+        self.frame += 1;
+        match self.inputs.front() {
+            Some(&(frame, input)) if frame == self.frame => {
+                self.inputs.pop_front();
+                Some(input)
+            }
+            Some(_) => Some(0),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Recording {
+    inputs: Vec<(usize, isize)>,
+}
+
+impl Recording {
+    fn load(path: &str) -> io::Result<Self> {
+        read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (frame, input) in &self.inputs {
+            writeln!(f, "{} {}", frame, input)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = String;
+
+    fn from_str(recording: &str) -> Result<Self, Self::Err> {
+        let mut inputs: Vec<(usize, isize)> = vec![];
+        for line in recording.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || format!("Invalid recording line {}", line);
+            let mut parts = line.split_whitespace();
+            let frame = parts
+                .next()
+                .and_then(|frame| frame.parse().ok())
+                .ok_or_else(invalid)?;
+            let input = parts
+                .next()
+                .and_then(|input| input.parse().ok())
+                .filter(|input: &isize| (-1..=1).contains(input))
+                .ok_or_else(invalid)?;
+            if parts.next().is_some() || inputs.last().is_some_and(|&(last, _)| frame <= last) {
+                return Err(invalid());
+            }
+            inputs.push((frame, input));
+        }
+        Ok(Recording { inputs })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Won,
+    Lost,
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Report {
+    frames: usize,
+    blocks_remaining: usize,
+    outcome: Outcome,
+    score: isize,
+    recording: Recording,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} after {} frames with {} blocks remaining, score {}",
+            self.outcome, self.frames, self.blocks_remaining, self.score
+        )
+    }
+}
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Joystick(isize),
+    Save,
+    Load,
+    Rewind(usize),
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(command: &str) -> Result<Self, Self::Err> {
+        let mut parts = command.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (None, _) | (Some("s"), None) => Ok(Command::Joystick(0)),
+            (Some("a"), None) => Ok(Command::Joystick(-1)),
+            (Some("d"), None) => Ok(Command::Joystick(1)),
+            (Some("w"), None) => Ok(Command::Save),
+            (Some("l"), None) => Ok(Command::Load),
+            (Some("r"), None) => Ok(Command::Rewind(1)),
+            (Some("r"), Some(frames)) => frames
+                .parse()
+                .map(Command::Rewind)
+                .map_err(|_| format!("Invalid frame count {}", frames)),
+            (Some("q"), None) => Ok(Command::Quit),
+            _ => Err(format!("Unknown command {}", command.trim())),
+        }
+    }
+}
+
+fn play<R: BufRead, W: Write>(arcade: &mut Arcade, input: R, mut output: W) -> io::Result<isize> {
+    let mut lines = input.lines();
+    let mut saved: Option<(Snapshot, usize)> = None;
+    let mut history: Vec<Snapshot> = vec![];
+    let mut state = arcade.run();
 
     loop {
-        match computer.run() {
-            ProgramState::Halted => break,
-            ProgramState::NeedInput => {
-                let ball_x = ball.unwrap();
-                let joystick_x = joystick.unwrap();
-                if ball_x < joystick_x {
-                    computer.push_input(-1);
-                } else if ball_x > joystick_x {
-                    computer.push_input(1);
-                } else {
-                    computer.push_input(0);
+        write!(output, "{}", CLEAR_SCREEN)?;
+        writeln!(output, "{}", arcade.screen().render())?;
+        writeln!(output, "Frame: {}", arcade.frames())?;
+        if state == ArcadeState::Halted {
+            writeln!(output, "Game over")?;
+            break;
+        }
+        write!(
+            output,
+            "[a] left [s] stay [d] right [w] save [l] load [r N] rewind [q] quit > "
+        )?;
+        output.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        match line.parse::<Command>() {
+            Ok(Command::Joystick(direction)) => {
+                history.push(arcade.snapshot());
+                arcade.push_joystick(direction);
+                state = arcade.run();
+            }
+            Ok(Command::Save) => saved = Some((arcade.snapshot(), history.len())),
+            Ok(Command::Load) => {
+                if let Some((snapshot, moves)) = &saved {
+                    arcade.restore(snapshot);
+                    history.truncate(*moves);
+                    state = ArcadeState::Frame;
+                }
+            }
+            Ok(Command::Rewind(frames)) => {
+                if frames > 0 && !history.is_empty() {
+                    let target = history.len().saturating_sub(frames);
+                    arcade.restore(&history[target]);
+                    history.truncate(target);
+                    state = ArcadeState::Frame;
                 }
             }
-            ProgramState::Output(out) => {
-                block.push(out);
+            Ok(Command::Quit) => break,
+            Err(error) => writeln!(output, "{}", error)?,
+        }
+    }
 
-                if block.len() == 3 {
-                    let id = block.pop().unwrap();
-                    let y = block.pop().unwrap();
-                    let x = block.pop().unwrap();
+    Ok(arcade.screen().score())
+}
 
-                    if x == -1 && y == 0 {
-                        score = id;
-                    } else if id == 3 {
-                        joystick = Some(x);
-                    } else if id == 4 {
-                        ball = Some(x);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl Tile {
+    fn from_id(id: isize) -> Self {
+        match id {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            n => panic!("Unknown tile {}", n),
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::Paddle => '-',
+            Tile::Ball => 'o',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Screen {
+    tiles: HashMap<(isize, isize), Tile>,
+    score: isize,
+}
+
+impl Screen {
+    fn draw(&mut self, x: isize, y: isize, id: isize) {
+        if x == -1 && y == 0 {
+            self.score = id;
+        } else {
+            self.tiles.insert((x, y), Tile::from_id(id));
+        }
+    }
+
+    fn tile(&self, x: isize, y: isize) -> Tile {
+        self.tiles.get(&(x, y)).copied().unwrap_or(Tile::Empty)
+    }
+
+    fn score(&self) -> isize {
+        self.score
+    }
+
+    fn count(&self, tile: Tile) -> usize {
+        self.tiles.values().filter(|&&t| t == tile).count()
+    }
+
+    fn find(&self, tile: Tile) -> Option<(isize, isize)> {
+        self.tiles
+            .iter()
+            .find(|(_, &t)| t == tile)
+            .map(|(&coordinates, _)| coordinates)
+    }
+
+    fn size(&self) -> (isize, isize) {
+        let width = self.tiles.keys().map(|c| c.0 + 1).max().unwrap_or(0);
+        let height = self.tiles.keys().map(|c| c.1 + 1).max().unwrap_or(0);
+        (width, height)
+    }
+
+    fn render(&self) -> String {
+        let (width, height) = self.size();
+        let mut lines: Vec<String> = (0..height)
+            .map(|y| (0..width).map(|x| self.tile(x, y).symbol()).collect())
+            .collect();
+        lines.push(format!("Score: {}", self.score));
+        lines.join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArcadeState {
+    Frame,
+    Halted,
+}
+
+#[derive(Clone)]
+struct Snapshot(Arcade);
+
+#[derive(Clone)]
+struct Arcade {
+    computer: IntcodeComputer,
+    screen: Screen,
+    pending: Vec<isize>,
+    frames: usize,
+    recording: Recording,
+}
+
+impl Arcade {
+    fn new(program: &[isize], free_play: bool) -> Self {
+        let mut program = program.to_vec();
+        if free_play {
+            program[0] = 2;
+        }
+
+        let mut computer = IntcodeComputer::new();
+        computer.push_instructions(program);
+
+        Arcade {
+            computer,
+            screen: Screen::default(),
+            pending: vec![],
+            frames: 0,
+            recording: Recording::default(),
+        }
+    }
+
+    fn run(&mut self) -> ArcadeState {
+        loop {
+            match self.computer.run() {
+                ProgramState::Halted => return ArcadeState::Halted,
+                ProgramState::NeedInput => {
+                    self.frames += 1;
+                    return ArcadeState::Frame;
+                }
+                ProgramState::Output(out) => {
+                    self.pending.push(out);
+                    if self.pending.len() == 3 {
+                        self.screen
+                            .draw(self.pending[0], self.pending[1], self.pending[2]);
+                        self.pending.clear();
                     }
                 }
             }
         }
     }
 
-    println!("{}", score);
+    fn push_joystick(&mut self, input: isize) {
+        self.recording.inputs.push((self.frames, input));
+        self.computer.push_input(input);
+    }
 
-    Ok(())
+    fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    fn frames(&self) -> usize {
+        self.frames
+    }
+
+    fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    fn run_with<J: Joystick>(&mut self, mut joystick: J, max_frames: usize) -> Report {
+        let mut played = 0;
+        let outcome = loop {
+            if self.run() == ArcadeState::Halted {
+                if self.screen.count(Tile::Block) == 0 {
+                    break Outcome::Won;
+                }
+                break Outcome::Lost;
+            }
+            if played >= max_frames {
+                break Outcome::Stopped;
+            }
+            match joystick.next(&self.screen) {
+                Some(input) => {
+                    played += 1;
+                    self.push_joystick(input);
+                }
+                None => break Outcome::Stopped,
+            }
+        };
+
+        Report {
+            frames: self.frames,
+            blocks_remaining: self.screen.count(Tile::Block),
+            outcome,
+            score: self.screen.score(),
+            recording: self.recording.clone(),
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot(self.clone())
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        *self = snapshot.0.clone();
+    }
 }
 
 enum ProgramState {
@@ -90,6 +572,7 @@ enum ProgramState {
     Output(isize),
 }
 
+#[derive(Clone)]
 struct IntcodeComputer {
     instructions: HashMap<isize, isize>,
     pointer: usize,
@@ -107,16 +590,9 @@ impl IntcodeComputer {
         }
     }
 
-    fn reset(&mut self) {
-        self.instructions.clear();
-        self.pointer = 0;
-        self.relative_base = 0;
-        self.inputs.clear();
-    }
-
     fn push_instructions(&mut self, program: Vec<isize>) {
-        for ind in 0..program.len() {
-            self.instructions.insert(ind as isize, program[ind]);
+        for (ind, value) in program.into_iter().enumerate() {
+            self.instructions.insert(ind as isize, value);
         }
     }
 
@@ -221,13 +697,13 @@ impl IntcodeComputer {
 
     fn get_paramter(&self, mode: &isize, ind: &isize) -> isize {
         match mode {
-            0 => match self.instructions.get(&ind) {
-                Some(x) => x.clone(),
+            0 => match self.instructions.get(ind) {
+                Some(x) => *x,
                 _ => 0,
             },
-            1 => ind.clone(),
-            2 => match self.instructions.get(&ind) {
-                Some(x) => (self.relative_base + x.clone()),
+            1 => *ind,
+            2 => match self.instructions.get(ind) {
+                Some(x) => self.relative_base + *x,
                 _ => self.relative_base,
             },
             n => panic!("Unknown mode {}", n),
@@ -236,8 +712,208 @@ impl IntcodeComputer {
 
     fn get_value(&self, param: &isize) -> isize {
         match self.instructions.get(param) {
-            Some(x) => x.clone(),
+            Some(x) => *x,
             _ => 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::compute_part1_result;
+    use super::compute_part2_result;
+    use super::play;
+    use super::Arcade;
+    use super::ArcadeState;
+    use super::Command;
+    use super::FollowBall;
+    use super::Joystick;
+    use super::Outcome;
+    use super::Playback;
+    use super::Predictive;
+    use super::RandomJoystick;
+    use super::Recording;
+    use super::Screen;
+    use super::Tile;
+    use std::env;
+    use std::fs::{self, read_to_string};
+    use std::process;
+
+    fn program() -> Vec<isize> {
+        read_to_string("src/bin/day13-care-package/input.txt")
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|line| line.parse::<isize>().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn it_should_draw_tiles_and_score() {
+        let mut screen = Screen::default();
+        screen.draw(1, 2, 3);
+        screen.draw(6, 5, 4);
+        screen.draw(-1, 0, 12345);
+
+        assert_eq!(screen.tile(1, 2), Tile::Paddle);
+        assert_eq!(screen.tile(6, 5), Tile::Ball);
+        assert_eq!(screen.tile(0, 0), Tile::Empty);
+        assert_eq!(screen.find(Tile::Ball), Some((6, 5)));
+        assert_eq!(screen.score(), 12345);
+    }
+
+    #[test]
+    fn it_should_stop_at_frame_boundaries() {
+        let program = vec![
+            104, 1, 104, 0, 104, 2, 3, 100, 104, -1, 104, 0, 1004, 100, 99,
+        ];
+        let mut arcade = Arcade::new(&program, false);
+
+        assert_eq!(arcade.run(), ArcadeState::Frame);
+        assert_eq!(arcade.screen().render(), " =\nScore: 0");
+        arcade.push_joystick(7);
+        assert_eq!(arcade.run(), ArcadeState::Halted);
+        assert_eq!(arcade.screen().score(), 7);
+        assert_eq!(arcade.frames(), 1);
+    }
+
+    #[test]
+    fn it_should_compute_part1_and_part2_results() {
+        assert_eq!(compute_part1_result(&program()), 205);
+        assert_eq!(compute_part2_result(&program()), 10292);
+    }
+
+    #[test]
+    fn it_should_parse_commands() {
+        assert_eq!("".parse::<Command>(), Ok(Command::Joystick(0)));
+        assert_eq!("a".parse::<Command>(), Ok(Command::Joystick(-1)));
+        assert_eq!("d".parse::<Command>(), Ok(Command::Joystick(1)));
+        assert_eq!("r".parse::<Command>(), Ok(Command::Rewind(1)));
+        assert_eq!("r 12".parse::<Command>(), Ok(Command::Rewind(12)));
+        assert!("r twelve".parse::<Command>().is_err());
+        assert!("x".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn it_should_rewind_and_reload_snapshots() {
+        let program = vec![3, 100, 104, -1, 104, 0, 4, 100, 3, 100, 99];
+        let mut arcade = Arcade::new(&program, false);
+        let mut output = vec![];
+        let score = play(&mut arcade, "d\nw\nr\na\nl\nq\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(score, 1);
+        assert_eq!(arcade.frames(), 2);
+
+        let mut arcade = Arcade::new(&program, false);
+        let score = play(&mut arcade, "d\nr\na\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(score, -1);
+        assert!(String::from_utf8(output).unwrap().contains("Score: -1"));
+    }
+
+    #[test]
+    fn it_should_rewind_from_the_loaded_snapshot() {
+        let program = vec![
+            3, 100, 104, -1, 104, 0, 4, 100, 3, 100, 104, -1, 104, 0, 4, 100, 3, 100, 99,
+        ];
+        let mut arcade = Arcade::new(&program, false);
+        let mut output = vec![];
+        let score = play(&mut arcade, "d\nw\na\nl\nr\nq\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(score, 0);
+        assert_eq!(arcade.frames(), 1);
+    }
+
+    #[test]
+    fn it_should_stay_put_without_a_ball() {
+        let mut screen = Screen::default();
+        screen.draw(3, 5, 3);
+
+        assert_eq!(FollowBall.next(&screen), Some(0));
+        screen.draw(1, 2, 4);
+        assert_eq!(FollowBall.next(&screen), Some(-1));
+    }
+
+    #[test]
+    fn it_should_aim_where_the_ball_will_land() {
+        let mut screen = Screen::default();
+        for y in 0..6 {
+            screen.draw(0, y, 1);
+            screen.draw(4, y, 1);
+        }
+        screen.draw(2, 5, 3);
+        screen.draw(2, 1, 4);
+        let mut predictive = Predictive::default();
+
+        assert_eq!(predictive.next(&screen), Some(0));
+        screen.draw(2, 1, 0);
+        screen.draw(3, 2, 4);
+        assert_eq!(Predictive::landing_x(&screen, (3, 2), (1, 1), 5), Some(1));
+        assert_eq!(predictive.next(&screen), Some(-1));
+        screen.draw(2, 3, 2);
+        assert_eq!(Predictive::landing_x(&screen, (3, 2), (1, 1), 5), None);
+    }
+
+    #[test]
+    fn it_should_report_game_outcomes() {
+        let follow = Arcade::new(&program(), true).run_with(FollowBall, 10_000);
+        assert_eq!(follow.outcome, Outcome::Won);
+        assert_eq!(follow.blocks_remaining, 0);
+        assert_eq!(follow.frames, follow.recording.inputs.len());
+
+        let playback =
+            Arcade::new(&program(), true).run_with(Playback::new(&follow.recording), 10_000);
+        assert_eq!(playback, follow);
+
+        let random = Arcade::new(&program(), true).run_with(RandomJoystick::new(1), 10_000);
+        assert_eq!(random.outcome, Outcome::Lost);
+        assert!(random.blocks_remaining > 0);
+
+        let stopped = Arcade::new(&program(), true)
+            .run_with(Playback::new(&"1 0\n2 0".parse().unwrap()), 10_000);
+        assert_eq!(stopped.outcome, Outcome::Stopped);
+        assert_eq!(stopped.frames, 3);
+
+        let limited = Arcade::new(&program(), true).run_with(FollowBall, 10);
+        assert_eq!(limited.outcome, Outcome::Stopped);
+        assert_eq!(limited.recording.inputs.len(), 10);
+    }
+
+    #[test]
+    fn it_should_parse_and_print_recordings() {
+        let recording: Recording = "1 0\n2 -1\n\n5 1\n".parse().unwrap();
+
+        assert_eq!(recording.inputs, vec![(1, 0), (2, -1), (5, 1)]);
+        assert_eq!(recording.to_string(), "1 0\n2 -1\n5 1\n");
+        assert!("1 0\n1 1".parse::<Recording>().is_err());
+        assert!("1 2".parse::<Recording>().is_err());
+        assert!("1".parse::<Recording>().is_err());
+    }
+
+    #[test]
+    fn it_should_replay_sparse_recordings() {
+        let mut playback = Playback::new(&"2 1\n4 -1".parse().unwrap());
+        let screen = Screen::default();
+        let inputs: Vec<Option<isize>> = (0..5).map(|_| playback.next(&screen)).collect();
+
+        assert_eq!(inputs, vec![Some(0), Some(1), Some(0), Some(-1), None]);
+    }
+
+    #[test]
+    fn it_should_replay_a_recorded_session_from_a_file() {
+        let path =
+            env::temp_dir().join(format!("day13-care-package-session-{}.txt", process::id()));
+        let path = path.to_str().unwrap();
+        let mut arcade = Arcade::new(&program(), true);
+        let original = arcade.run_with(Predictive::default(), 10_000);
+        arcade.recording().save(path).unwrap();
+
+        let recording = Recording::load(path).unwrap();
+        let replayed = Arcade::new(&program(), true).run_with(Playback::new(&recording), 10_000);
+
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(replayed.score, original.score);
+        assert_eq!(replayed, original);
+    }
+}
@@ -1,192 +1,491 @@
 use regex::Regex;
-use std::cmp;
 use std::collections::HashMap;
-use std::{fs::read_to_string, io};
+use std::fmt;
+use std::{env, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
     let file = read_to_string("src/bin/day14-space-stoichiometry/input.txt")?;
 
-    println!("{}", part1(&file));
-    println!("{}", part2(&file));
+    match env::args().nth(1).as_deref() {
+        Some("requirements") => {
+            let target = env::args().nth(2).unwrap_or_else(|| "FUEL".to_owned());
+            let quantity = env::args().nth(3).map_or(1, |n| n.parse().unwrap());
+            let graph = ReactionGraph::parse(&file, &["ORE"]).map_err(invalid_data)?;
+            for (raw, amount) in graph
+                .requirements(&target, quantity)
+                .map_err(invalid_data)?
+            {
+                println!("{} {}", amount, raw);
+            }
+        }
+        Some("max") => {
+            let target = env::args().nth(2).unwrap_or_else(|| "FUEL".to_owned());
+            let budgets: Vec<(String, usize)> = env::args()
+                .skip(3)
+                .map(|budget| {
+                    let mut parts = budget.splitn(2, '=');
+                    let raw = parts.next().unwrap().to_owned();
+                    (raw, parts.next().unwrap().parse().unwrap())
+                })
+                .collect();
+            let raw: Vec<&str> = budgets.iter().map(|(raw, _)| raw.as_str()).collect();
+            let budgets: Vec<(&str, usize)> = budgets
+                .iter()
+                .map(|(raw, budget)| (raw.as_str(), *budget))
+                .collect();
+            let graph = ReactionGraph::parse(&file, &raw).map_err(invalid_data)?;
+            println!(
+                "{}",
+                graph.max_output(&target, &budgets).map_err(invalid_data)?
+            );
+        }
+        Some(command @ "plan") | Some(command @ "dot") => {
+            let target = env::args().nth(2).unwrap_or_else(|| "FUEL".to_owned());
+            let quantity = env::args().nth(3).map_or(1, |n| n.parse().unwrap());
+            let graph = ReactionGraph::parse(&file, &["ORE"]).map_err(invalid_data)?;
+            let plan = graph.plan(&target, quantity).map_err(invalid_data)?;
+            if command == "plan" {
+                print!("{}", plan.to_table());
+                let leftovers: Vec<String> = plan
+                    .leftovers()
+                    .iter()
+                    .map(|(chemical, leftover)| format!("{} {}", leftover, chemical))
+                    .collect();
+                println!("leftovers: {}", leftovers.join(", "));
+            } else {
+                print!("{}", graph.to_dot(Some(&plan)));
+            }
+        }
+        _ => {
+            println!("{}", part1(&file).map_err(invalid_data)?);
+            println!("{}", part2(&file).map_err(invalid_data)?);
+        }
+    }
 
     Ok(())
 }
 
-fn part1(input: &str) -> usize {
-    do_reactions(
-        &input
-            .trim()
-            .split('\n')
-            .map(|l| parse_reaction(l))
-            .collect(),
-        &mut vec![Chemical::new("FUEL".to_owned(), 1)],
-    )
+fn invalid_data(error: ReactionError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
 }
 
-fn part2(input: &str) -> usize {
-    let ore_needed_for_1_fuel = do_reactions(
-        &input
-            .trim()
-            .split('\n')
-            .map(|l| parse_reaction(l))
-            .collect(),
-        &mut vec![Chemical::new("FUEL".to_owned(), 1)],
-    );
-
-    let mut min_fuel = 1000000000000 / ore_needed_for_1_fuel;
-    let mut max_fuel = 2 * min_fuel;
-    let mut target_fuel = min_fuel + (max_fuel - min_fuel) / 2;
-
-    loop {
-        let ore_needed = do_reactions(
-            &input
-                .trim()
-                .split('\n')
-                .map(|l| parse_reaction(l))
-                .collect(),
-            &mut vec![Chemical::new("FUEL".to_owned(), target_fuel)],
-        );
+fn part1(input: &str) -> Result<usize, ReactionError> {
+    let graph = ReactionGraph::parse(input, &["ORE"])?;
+    graph.raw_required("ORE", "FUEL", 1)
+}
 
-        if ore_needed > 1000000000000 {
-            max_fuel = target_fuel;
-        } else if ore_needed < 1000000000000 {
-            min_fuel = target_fuel;
-        } else {
-            break;
-        }
-        target_fuel = min_fuel + (max_fuel - min_fuel) / 2;
+const ORE_BUDGET: usize = 1_000_000_000_000;
 
-        if target_fuel == min_fuel || target_fuel == max_fuel {
-            break;
+fn part2(input: &str) -> Result<usize, ReactionError> {
+    let graph = ReactionGraph::parse(input, &["ORE"])?;
+    graph.max_output("FUEL", &[("ORE", ORE_BUDGET)])
+}
+
+fn parse_chemical(line: &str) -> Result<Chemical, ReactionError> {
+    let chemical_reg = Regex::new(r"^([0-9]+)\s([A-Z]+)$").unwrap();
+    let invalid = || ReactionError::InvalidReaction(line.to_owned());
+    let captures = chemical_reg.captures(line.trim()).ok_or_else(invalid)?;
+    Ok(Chemical::new(
+        captures[2].to_owned(),
+        captures[1].parse::<usize>().map_err(|_| invalid())?,
+    ))
+}
+
+fn parse_reaction(line: &str) -> Result<Reaction, ReactionError> {
+    let reaction_reg = Regex::new(r"^(.*)\s=>\s(.*)$").unwrap();
+    let invalid = || ReactionError::InvalidReaction(line.trim().to_owned());
+    let captures = reaction_reg.captures(line.trim()).ok_or_else(invalid)?;
+    let inputs = captures[1]
+        .split(',')
+        .map(|c| c.trim())
+        .map(parse_chemical)
+        .collect::<Result<Vec<Chemical>, ReactionError>>()
+        .map_err(|_| invalid())?;
+    let output = parse_chemical(&captures[2]).map_err(|_| invalid())?;
+    Ok(Reaction::new(inputs, output))
+}
+
+#[derive(Debug, PartialEq)]
+struct Chemical {
+    kind: String,
+    quantity: usize,
+}
+
+impl Chemical {
+    fn new(kind: String, quantity: usize) -> Self {
+        Chemical { kind, quantity }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Reaction {
+    inputs: Vec<Chemical>,
+    output: Chemical,
+}
+
+impl Reaction {
+    fn new(inputs: Vec<Chemical>, output: Chemical) -> Self {
+        Reaction { inputs, output }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ReactionError {
+    InvalidReaction(String),
+    UnknownChemical(String),
+    MissingProducer(String),
+    MultipleProducers(String),
+    Cycle(Vec<String>),
+    NotRaw(String),
+    Unbounded(String),
+    Overflow,
+}
+
+impl fmt::Display for ReactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReactionError::InvalidReaction(line) => write!(f, "Invalid reaction {}", line),
+            ReactionError::UnknownChemical(name) => write!(f, "Unknown chemical {}", name),
+            ReactionError::MissingProducer(name) => write!(f, "No reaction produces {}", name),
+            ReactionError::MultipleProducers(name) => {
+                write!(f, "More than one reaction produces {}", name)
+            }
+            ReactionError::Cycle(names) => write!(f, "Reaction cycle between {}", names.join(", ")),
+            ReactionError::NotRaw(name) => write!(f, "{} is not a raw material", name),
+            ReactionError::Unbounded(name) => write!(f, "{} can be produced without limit", name),
+            ReactionError::Overflow => write!(f, "Quantity does not fit in the integer type"),
         }
     }
+}
 
-    target_fuel
+#[derive(Debug, Clone, PartialEq)]
+struct IndexedReaction {
+    inputs: Vec<(usize, usize)>,
+    output: (usize, usize),
 }
 
-fn parse_chemical(line: &str) -> Chemical {
-    let chemical_reg = Regex::new(r"([0-9]+)\s([A-Z]+)").unwrap();
-    let captures = chemical_reg.captures_iter(line).next().unwrap();
-    Chemical::new(
-        captures[2].to_owned(),
-        captures[1].parse::<usize>().unwrap(),
-    )
+#[derive(Debug, Clone, PartialEq)]
+struct PlanStep {
+    chemical: String,
+    raw: bool,
+    runs: usize,
+    produced: usize,
+    consumed: usize,
+    leftover: usize,
 }
 
-fn do_reactions(reactions: &Vec<Reaction>, what_is_needed: &mut Vec<Chemical>) -> usize {
-    let mut ore_needed = 0;
-    let mut over_production_map: HashMap<String, usize> = HashMap::new();
-    loop {
-        match what_is_needed.pop() {
-            Some(chemical_to_produce) => {
-                if chemical_to_produce.quantity == 0 {
-                    continue;
-                }
+#[derive(Debug, Clone, PartialEq)]
+struct Plan {
+    steps: Vec<PlanStep>,
+}
 
-                let reaction = reactions
-                    .iter()
-                    .find(|r| chemical_to_produce.kind == r.output.kind)
-                    .unwrap();
-
-                let times_reaction_needed =
-                    match chemical_to_produce.quantity % reaction.output.quantity {
-                        0 => chemical_to_produce.quantity / reaction.output.quantity,
-                        _ => chemical_to_produce.quantity / reaction.output.quantity + 1,
-                    };
-
-                let over_production = times_reaction_needed * reaction.output.quantity
-                    - chemical_to_produce.quantity
-                    + match over_production_map.get(&chemical_to_produce.kind) {
-                        Some(n) => n,
-                        _ => &0,
-                    };
-
-                if over_production > 0 {
-                    over_production_map.insert(chemical_to_produce.kind, over_production);
-                }
+impl Plan {
+    fn step(&self, chemical: &str) -> Option<&PlanStep> {
+        self.steps.iter().find(|step| step.chemical == chemical)
+    }
 
-                for chemical_input in reaction.inputs.iter() {
-                    let mut quantity_needed = chemical_input.quantity * times_reaction_needed;
+    fn leftovers(&self) -> Vec<(&str, usize)> {
+        self.steps
+            .iter()
+            .filter(|step| step.leftover > 0)
+            .map(|step| (step.chemical.as_str(), step.leftover))
+            .collect()
+    }
 
-                    if chemical_input.kind == "ORE" {
-                        ore_needed += quantity_needed;
-                        continue;
-                    }
+    fn to_table(&self) -> String {
+        let mut rows = vec![vec![
+            "chemical".to_owned(),
+            "runs".to_owned(),
+            "produced".to_owned(),
+            "consumed".to_owned(),
+            "leftover".to_owned(),
+        ]];
+        for step in &self.steps {
+            rows.push(vec![
+                step.chemical.clone(),
+                if step.raw {
+                    "raw".to_owned()
+                } else {
+                    step.runs.to_string()
+                },
+                step.produced.to_string(),
+                step.consumed.to_string(),
+                step.leftover.to_string(),
+            ]);
+        }
 
-                    let quantity_over_produced = match over_production_map.get(&chemical_input.kind)
-                    {
-                        Some(n) => n,
-                        _ => &0,
-                    };
+        let widths: Vec<usize> = (0..5)
+            .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+            .collect();
+        let mut table = String::new();
+        for row in rows {
+            let mut line = format!("{:<width$}", row[0], width = widths[0]);
+            for (cell, width) in row.iter().zip(widths.iter()).skip(1) {
+                line.push_str(&format!("  {:>width$}", cell, width = width));
+            }
+            table.push_str(&line);
+            table.push('\n');
+        }
+        table
+    }
+}
 
-                    let consumed_quantity =
-                        cmp::min(quantity_needed, quantity_over_produced.clone());
+#[derive(Debug, Clone)]
+struct ReactionGraph {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
+    reactions: Vec<IndexedReaction>,
+    producers: Vec<Option<usize>>,
+    order: Vec<usize>,
+}
 
-                    quantity_needed -= consumed_quantity;
+impl ReactionGraph {
+    fn parse(input: &str, raw: &[&str]) -> Result<Self, ReactionError> {
+        let reactions = input
+            .trim()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_reaction)
+            .collect::<Result<Vec<Reaction>, ReactionError>>()?;
+        ReactionGraph::new(&reactions, raw)
+    }
 
-                    if quantity_over_produced > &0 {
-                        over_production_map.insert(
-                            chemical_input.kind.clone(),
-                            quantity_over_produced - consumed_quantity,
-                        );
-                    }
+    fn new(reactions: &[Reaction], raw: &[&str]) -> Result<Self, ReactionError> {
+        let mut graph = ReactionGraph {
+            names: vec![],
+            indices: HashMap::new(),
+            reactions: vec![],
+            producers: vec![],
+            order: vec![],
+        };
+
+        for name in raw {
+            graph.intern(name);
+        }
+        for reaction in reactions {
+            let output = (
+                graph.intern(&reaction.output.kind),
+                reaction.output.quantity,
+            );
+            let inputs = reaction
+                .inputs
+                .iter()
+                .map(|chemical| (graph.intern(&chemical.kind), chemical.quantity))
+                .collect();
+            if graph.producers[output.0].is_some() {
+                return Err(ReactionError::MultipleProducers(
+                    reaction.output.kind.clone(),
+                ));
+            }
+            graph.producers[output.0] = Some(graph.reactions.len());
+            graph.reactions.push(IndexedReaction { inputs, output });
+        }
+
+        for (chemical, producer) in graph.producers.iter().enumerate() {
+            let name = &graph.names[chemical];
+            if producer.is_none() && !raw.contains(&name.as_str()) {
+                return Err(ReactionError::MissingProducer(name.clone()));
+            }
+        }
+
+        graph.order = graph.topological_order()?;
+        Ok(graph)
+    }
 
-                    match what_is_needed
-                        .iter_mut()
-                        .find(|c| c.kind == chemical_input.kind)
-                    {
-                        Some(chemical) => chemical.quantity += quantity_needed,
-                        _ => what_is_needed
-                            .push(Chemical::new(chemical_input.kind.clone(), quantity_needed)),
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.indices.get(name) {
+            return index;
+        }
+        self.names.push(name.to_owned());
+        self.producers.push(None);
+        self.indices.insert(name.to_owned(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    fn index(&self, name: &str) -> Result<usize, ReactionError> {
+        self.indices
+            .get(name)
+            .copied()
+            .ok_or_else(|| ReactionError::UnknownChemical(name.to_owned()))
+    }
+
+    fn topological_order(&self) -> Result<Vec<usize>, ReactionError> {
+        let mut consumers = vec![0; self.names.len()];
+        for reaction in &self.reactions {
+            for &(input, _) in &reaction.inputs {
+                consumers[input] += 1;
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..self.names.len())
+            .filter(|&chemical| consumers[chemical] == 0)
+            .collect();
+        let mut order = vec![];
+        while let Some(chemical) = ready.pop() {
+            order.push(chemical);
+            if let Some(producer) = self.producers[chemical] {
+                for &(input, _) in &self.reactions[producer].inputs {
+                    consumers[input] -= 1;
+                    if consumers[input] == 0 {
+                        ready.push(input);
                     }
                 }
             }
-            _ => break,
         }
+
+        if order.len() < self.names.len() {
+            let mut cycle: Vec<String> = (0..self.names.len())
+                .filter(|&chemical| consumers[chemical] > 0 && self.producers[chemical].is_some())
+                .map(|chemical| self.names[chemical].clone())
+                .collect();
+            cycle.sort();
+            return Err(ReactionError::Cycle(cycle));
+        }
+        Ok(order)
     }
 
-    ore_needed
-}
+    fn needs(&self, target: usize, quantity: usize) -> Result<Vec<usize>, ReactionError> {
+        let mut needed = vec![0; self.names.len()];
+        needed[target] = quantity;
+
+        for &chemical in &self.order {
+            if let Some(producer) = self.producers[chemical] {
+                let reaction = &self.reactions[producer];
+                let runs = needed[chemical].div_ceil(reaction.output.1);
+                for &(input, amount) in &reaction.inputs {
+                    needed[input] = runs
+                        .checked_mul(amount)
+                        .and_then(|amount| needed[input].checked_add(amount))
+                        .ok_or(ReactionError::Overflow)?;
+                }
+            }
+        }
+        Ok(needed)
+    }
 
-fn parse_reaction(line: &str) -> Reaction {
-    let reaction_reg = Regex::new(r"(.*)\s=>\s(.*)").unwrap();
-    let captures = reaction_reg.captures_iter(line).next().unwrap();
-    let inputs: Vec<Chemical> = captures[1]
-        .split(",")
-        .map(|c| c.trim())
-        .map(|c| parse_chemical(c))
-        .collect();
-    let output: Chemical = parse_chemical(&captures[2]);
-    Reaction::new(inputs, output)
-}
+    fn requirements(
+        &self,
+        target: &str,
+        quantity: usize,
+    ) -> Result<Vec<(&str, usize)>, ReactionError> {
+        let needed = self.needs(self.index(target)?, quantity)?;
+        Ok((0..self.names.len())
+            .filter(|&chemical| self.producers[chemical].is_none())
+            .map(|chemical| (self.names[chemical].as_str(), needed[chemical]))
+            .collect())
+    }
 
-#[derive(Debug, PartialEq)]
-struct Chemical {
-    kind: String,
-    quantity: usize,
-}
+    fn plan(&self, target: &str, quantity: usize) -> Result<Plan, ReactionError> {
+        let needed = self.needs(self.index(target)?, quantity)?;
+        let steps = self
+            .order
+            .iter()
+            .filter(|&&chemical| needed[chemical] > 0)
+            .map(|&chemical| {
+                let (runs, produced) = match self.producers[chemical] {
+                    Some(producer) => {
+                        let output = self.reactions[producer].output.1;
+                        let runs = needed[chemical].div_ceil(output);
+                        let produced = runs.checked_mul(output).ok_or(ReactionError::Overflow)?;
+                        (runs, produced)
+                    }
+                    None => (0, needed[chemical]),
+                };
+                Ok(PlanStep {
+                    chemical: self.names[chemical].clone(),
+                    raw: self.producers[chemical].is_none(),
+                    runs,
+                    produced,
+                    consumed: needed[chemical],
+                    leftover: produced - needed[chemical],
+                })
+            })
+            .collect::<Result<Vec<PlanStep>, ReactionError>>()?;
+        Ok(Plan { steps })
+    }
 
-impl Chemical {
-    fn new(kind: String, quantity: usize) -> Self {
-        Chemical {
-            kind: kind,
-            quantity: quantity,
+    fn to_dot(&self, plan: Option<&Plan>) -> String {
+        let step_of = |chemical: usize| plan.and_then(|plan| plan.step(&self.names[chemical]));
+        let mut dot = String::from("digraph reactions {\n    rankdir=LR;\n");
+        for &chemical in &self.order {
+            let name = &self.names[chemical];
+            match (plan, step_of(chemical)) {
+                (Some(_), None) => continue,
+                (Some(_), Some(step)) if step.leftover > 0 => dot.push_str(&format!(
+                    "    \"{}\" [label=\"{}\\n{} made, {} left\"];\n",
+                    name, name, step.produced, step.leftover
+                )),
+                (Some(_), Some(step)) => dot.push_str(&format!(
+                    "    \"{}\" [label=\"{}\\n{} made\"];\n",
+                    name, name, step.produced
+                )),
+                (None, _) => dot.push_str(&format!("    \"{}\";\n", name)),
+            }
+        }
+        for reaction in &self.reactions {
+            let (output, produced) = reaction.output;
+            for &(input, amount) in &reaction.inputs {
+                let label = match step_of(output) {
+                    Some(step) => (step.runs * amount).to_string(),
+                    None if plan.is_some() => continue,
+                    None => format!("{} per {}", amount, produced),
+                };
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                    self.names[input], self.names[output], label
+                ));
+            }
         }
+        dot.push_str("}\n");
+        dot
     }
-}
 
-#[derive(Debug, PartialEq)]
-struct Reaction {
-    inputs: Vec<Chemical>,
-    output: Chemical,
-}
+    fn max_output(&self, target: &str, budgets: &[(&str, usize)]) -> Result<usize, ReactionError> {
+        let target = self.index(target)?;
+        let mut available = vec![0; self.names.len()];
+        for &(raw, budget) in budgets {
+            let raw_index = self.index(raw)?;
+            if self.producers[raw_index].is_some() {
+                return Err(ReactionError::NotRaw(raw.to_owned()));
+            }
+            available[raw_index] = budget;
+        }
 
-impl Reaction {
-    fn new(inputs: Vec<Chemical>, output: Chemical) -> Self {
-        Reaction {
-            inputs: inputs,
-            output: output,
+        let fits = |quantity: usize| match self.needs(target, quantity) {
+            Ok(needs) => needs.iter().enumerate().all(|(chemical, &needed)| {
+                self.producers[chemical].is_some() || needed <= available[chemical]
+            }),
+            Err(_) => false,
+        };
+
+        let mut low = 0;
+        let mut high = 1;
+        while fits(high) {
+            if high > usize::MAX / 4 {
+                return Err(ReactionError::Unbounded(self.names[target].clone()));
+            }
+            low = high;
+            high *= 2;
+        }
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if fits(middle) {
+                low = middle;
+            } else {
+                high = middle;
+            }
         }
+        Ok(low)
+    }
+
+    fn raw_required(
+        &self,
+        raw: &str,
+        target: &str,
+        quantity: usize,
+    ) -> Result<usize, ReactionError> {
+        let raw = self.index(raw)?;
+        Ok(self.needs(self.index(target)?, quantity)?[raw])
     }
 }
 
@@ -196,27 +495,49 @@ mod tests {
     use super::part1;
     use super::part2;
     use super::Chemical;
+    use super::PlanStep;
     use super::Reaction;
+    use super::ReactionError;
+    use super::ReactionGraph;
 
     #[test]
     fn it_should_parse_input_lines() {
         assert_eq!(
-            parse_reaction(&"10 ORE => 10 A"),
-            Reaction::new(
+            parse_reaction("10 ORE => 10 A"),
+            Ok(Reaction::new(
                 vec![Chemical::new("ORE".to_owned(), 10)],
                 Chemical::new("A".to_owned(), 10)
-            )
+            ))
         );
 
         assert_eq!(
-            parse_reaction(&"7 A, 1 E => 1 FUEL"),
-            Reaction::new(
+            parse_reaction("7 A, 1 E => 1 FUEL"),
+            Ok(Reaction::new(
                 vec![
                     Chemical::new("A".to_owned(), 7),
                     Chemical::new("E".to_owned(), 1)
                 ],
                 Chemical::new("FUEL".to_owned(), 1)
-            )
+            ))
+        );
+    }
+
+    #[test]
+    fn it_should_reject_malformed_input_lines() {
+        for line in &[
+            "10 ORE -> 10 A",
+            "10 ORE => A",
+            "x ORE => 1 A",
+            "7 A,, 1 E => 1 FUEL",
+        ] {
+            assert_eq!(
+                parse_reaction(line),
+                Err(ReactionError::InvalidReaction((*line).to_owned()))
+            );
+        }
+        assert_eq!(
+            ReactionGraph::parse("10 ORE => 10 A\n7 A => FUEL", &["ORE"]).err(),
+            Some(ReactionError::InvalidReaction("7 A => FUEL".to_owned()))
         );
     }
 
@@ -224,7 +545,7 @@ mod tests {
     fn it_should_compute_the_ore_quantity_needed_1() {
         assert_eq!(
             part1(
-                &"
+                "
             10 ORE => 10 A
             1 ORE => 1 B
             7 A, 1 B => 1 C
@@ -233,7 +554,7 @@ mod tests {
             7 A, 1 E => 1 FUEL
         "
             ),
-            31
+            Ok(31)
         );
     }
 
@@ -241,7 +562,7 @@ mod tests {
     fn it_should_compute_the_ore_quantity_needed_2() {
         assert_eq!(
             part1(
-                &"
+                "
             9 ORE => 2 A
             8 ORE => 3 B
             7 ORE => 5 C
@@ -251,7 +572,7 @@ mod tests {
             2 AB, 3 BC, 4 CA => 1 FUEL
         "
             ),
-            165
+            Ok(165)
         );
     }
 
@@ -259,7 +580,7 @@ mod tests {
     fn it_should_compute_the_ore_quantity_needed_3() {
         assert_eq!(
             part1(
-                &"
+                "
             157 ORE => 5 NZVS
             165 ORE => 6 DCFZ
             44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
@@ -271,7 +592,7 @@ mod tests {
             3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT
         "
             ),
-            13312
+            Ok(13312)
         );
     }
 
@@ -279,7 +600,7 @@ mod tests {
     fn it_should_compute_the_ore_quantity_needed_4() {
         assert_eq!(
             part1(
-                &"
+                "
             2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG
             17 NVRVD, 3 JNWZP => 8 VPVL
             53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL
@@ -294,7 +615,7 @@ mod tests {
             176 ORE => 6 VJHF
         "
             ),
-            180697
+            Ok(180697)
         );
     }
 
@@ -302,7 +623,7 @@ mod tests {
     fn it_should_compute_the_ore_quantity_needed_5() {
         assert_eq!(
             part1(
-                &"
+                "
             171 ORE => 8 CNZTR
             7 ZLQW, 3 BMBT, 9 XCVML, 26 XMNCP, 1 WPTQ, 2 MZWV, 1 RJRHP => 4 PLWSL
             114 ORE => 4 BHXH
@@ -322,7 +643,7 @@ mod tests {
             5 BHXH, 4 VRPVC => 5 LTCX
         "
             ),
-            2210736
+            Ok(2210736)
         );
     }
 
@@ -330,7 +651,7 @@ mod tests {
     fn it_should_compute_the_fuel_output_quantity_1() {
         assert_eq!(
             part2(
-                &"
+                "
             157 ORE => 5 NZVS
             165 ORE => 6 DCFZ
             44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL
@@ -342,7 +663,7 @@ mod tests {
             3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT
         "
             ),
-            82892753
+            Ok(82892753)
         );
     }
 
@@ -350,7 +671,7 @@ mod tests {
     fn it_should_compute_the_fuel_output_quantity_2() {
         assert_eq!(
             part2(
-                &"
+                "
             2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG
             17 NVRVD, 3 JNWZP => 8 VPVL
             53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL
@@ -365,7 +686,7 @@ mod tests {
             176 ORE => 6 VJHF
         "
             ),
-            5586022
+            Ok(5586022)
         );
     }
 
@@ -373,7 +694,7 @@ mod tests {
     fn it_should_compute_the_fuel_output_quantity_3() {
         assert_eq!(
             part2(
-                &"
+                "
             171 ORE => 8 CNZTR
             7 ZLQW, 3 BMBT, 9 XCVML, 26 XMNCP, 1 WPTQ, 2 MZWV, 1 RJRHP => 4 PLWSL
             114 ORE => 4 BHXH
@@ -393,7 +714,178 @@ mod tests {
             5 BHXH, 4 VRPVC => 5 LTCX
         "
             ),
-            460664
+            Ok(460664)
+        );
+    }
+
+    #[test]
+    fn it_should_list_raw_requirements() {
+        let graph = ReactionGraph::parse(
+            "
+            9 ORE => 2 A
+            8 ORE => 3 B
+            7 ORE, 1 WATER => 5 C
+            3 A, 4 B => 1 AB
+            5 B, 7 C => 1 BC
+            4 C, 1 A => 1 CA
+            2 AB, 3 BC, 4 CA => 1 FUEL
+        ",
+            &["ORE", "WATER"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            graph.requirements("FUEL", 1),
+            Ok(vec![("ORE", 165), ("WATER", 8)])
+        );
+        assert_eq!(graph.raw_required("ORE", "BC", 1), Ok(16 + 14));
+        assert_eq!(
+            graph.raw_required("ORE", "GOLD", 1),
+            Err(ReactionError::UnknownChemical("GOLD".to_owned()))
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_reaction_graphs() {
+        assert_eq!(
+            ReactionGraph::parse("10 ORE => 10 A\n7 A, 1 B => 1 FUEL", &["ORE"]).err(),
+            Some(ReactionError::MissingProducer("B".to_owned()))
+        );
+        assert_eq!(
+            ReactionGraph::parse("10 ORE => 10 A\n1 ORE => 1 A", &["ORE"]).err(),
+            Some(ReactionError::MultipleProducers("A".to_owned()))
+        );
+        assert_eq!(
+            ReactionGraph::parse(
+                "10 ORE, 1 C => 10 A\n1 A => 1 B\n1 B => 1 C\n1 C => 1 FUEL",
+                &["ORE"]
+            )
+            .err(),
+            Some(ReactionError::Cycle(vec![
+                "A".to_owned(),
+                "B".to_owned(),
+                "C".to_owned()
+            ]))
+        );
+    }
+
+    #[test]
+    fn it_should_maximize_output_for_several_budgets() {
+        let graph = ReactionGraph::parse(
+            "
+            9 ORE => 2 A
+            8 ORE => 3 B
+            7 ORE, 1 WATER => 5 C
+            3 A, 4 B => 1 AB
+            5 B, 7 C => 1 BC
+            4 C, 1 A => 1 CA
+            2 AB, 3 BC, 4 CA => 1 FUEL
+        ",
+            &["ORE", "WATER"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            graph.max_output("FUEL", &[("ORE", 165), ("WATER", 8)]),
+            Ok(1)
+        );
+        assert_eq!(
+            graph.max_output("FUEL", &[("ORE", 164), ("WATER", 8)]),
+            Ok(0)
+        );
+        assert_eq!(graph.max_output("FUEL", &[("ORE", 1_000_000)]), Ok(0));
+        assert_eq!(graph.max_output("C", &[("ORE", 70), ("WATER", 3)]), Ok(15));
+        assert_eq!(
+            graph.max_output("A", &[("ORE", 1_000_000_000_000)]),
+            Ok(222222222222)
+        );
+        assert_eq!(
+            graph.max_output("A", &[("ORE", usize::MAX)]),
+            Ok(usize::MAX / 9 * 2)
+        );
+        assert_eq!(
+            graph.requirements("FUEL", usize::MAX / 2),
+            Err(ReactionError::Overflow)
+        );
+        assert_eq!(
+            graph.raw_required("ORE", "A", usize::MAX),
+            Err(ReactionError::Overflow)
+        );
+        assert_eq!(
+            graph.max_output("FUEL", &[("A", 10)]),
+            Err(ReactionError::NotRaw("A".to_owned()))
+        );
+    }
+
+    #[test]
+    fn it_should_plan_production_with_leftovers() {
+        let graph = ReactionGraph::parse(
+            "
+            10 ORE => 10 A
+            1 ORE => 1 B
+            7 A, 1 B => 1 C
+            7 A, 1 C => 1 D
+            7 A, 1 D => 1 E
+            7 A, 1 E => 1 FUEL
+        ",
+            &["ORE"],
+        )
+        .unwrap();
+        let plan = graph.plan("FUEL", 1).unwrap();
+
+        assert_eq!(
+            plan.step("A"),
+            Some(&PlanStep {
+                chemical: "A".to_owned(),
+                raw: false,
+                runs: 3,
+                produced: 30,
+                consumed: 28,
+                leftover: 2,
+            })
+        );
+        assert_eq!(plan.step("ORE").map(|step| step.consumed), Some(31));
+        assert_eq!(plan.leftovers(), vec![("A", 2)]);
+        assert_eq!(
+            graph.plan("A", usize::MAX - 1),
+            Err(ReactionError::Overflow)
+        );
+        assert_eq!(
+            plan.to_table(),
+            "\
+chemical  runs  produced  consumed  leftover
+FUEL         1         1         1         0
+E            1         1         1         0
+D            1         1         1         0
+C            1         1         1         0
+B            1         1         1         0
+A            3        30        28         2
+ORE        raw        31        31         0
+"
+        );
+    }
+
+    #[test]
+    fn it_should_export_the_reaction_network_as_dot() {
+        let graph =
+            ReactionGraph::parse("10 ORE => 10 A\n7 A => 1 FUEL\n1 ORE => 1 B", &["ORE"]).unwrap();
+        let plan = graph.plan("FUEL", 2).unwrap();
+
+        assert_eq!(
+            graph.to_dot(Some(&plan)),
+            "\
+digraph reactions {
+    rankdir=LR;
+    \"FUEL\" [label=\"FUEL\\n2 made\"];
+    \"A\" [label=\"A\\n20 made, 6 left\"];
+    \"ORE\" [label=\"ORE\\n20 made\"];
+    \"ORE\" -> \"A\" [label=\"20\"];
+    \"A\" -> \"FUEL\" [label=\"14\"];
+}
+"
         );
+        assert!(graph
+            .to_dot(None)
+            .contains("    \"ORE\" -> \"B\" [label=\"1 per 1\"];\n"));
     }
 }
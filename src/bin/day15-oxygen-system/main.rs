@@ -1,614 +1,370 @@
+use pathfinding::prelude::{bfs, dijkstra_all};
 use std::collections::HashMap;
-use std::{collections::VecDeque, fs::read_to_string, io};
+use std::str::FromStr;
+use std::{collections::VecDeque, env, fs, fs::read_to_string, io};
 
 fn main() -> io::Result<()> {
-    let program: Vec<isize> = read_to_string("src/bin/day15-oxygen-system/input.txt")?
-        .trim()
-        .split(',')
-        .map(|line| line.parse::<isize>().unwrap())
-        .collect();
-
-    let mut maze: HashMap<String, Coordinates> = HashMap::new();
-    let oxygen_center = walk_program(&program, &mut maze).unwrap();
-    println!("{}", oxygen_center.distance);
-    println!("{}", spread_oxygen(&mut maze, &oxygen_center));
-
-    Ok(())
-}
-
-fn spread_oxygen(maze: &mut HashMap<String, Coordinates>, oxygen_center: &Coordinates) -> usize {
-    let oxygenated = Coordinates::new(
-        oxygen_center.x,
-        oxygen_center.y,
-        None,
-        Some(CoordinatesKind::Oxygen),
-    );
-    let mut oxygen_coordinates: Vec<Coordinates> = vec![oxygenated];
-    maze.insert(
-        oxygen_center.to_maze_key(),
-        Coordinates::new(
-            oxygen_center.x,
-            oxygen_center.y,
-            None,
-            Some(CoordinatesKind::Oxygen),
-        ),
-    );
-
-    loop {
-        match oxygen_coordinates.pop() {
-            Some(coordinates) => {
-                match maze.get(&get_north_key(&coordinates)).unwrap().kind {
-                    CoordinatesKind::DeadEnd => {
-                        let oxygenated = get_north_coordinates(&coordinates);
-                        maze.insert(oxygenated.to_maze_key(), oxygenated);
-                        oxygen_coordinates.push(get_north_coordinates(&coordinates));
-                    }
-                    _ => (),
-                }
-
-                match maze.get(&get_east_key(&coordinates)).unwrap().kind {
-                    CoordinatesKind::DeadEnd => {
-                        let oxygenated = get_east_coordinates(&coordinates);
-                        maze.insert(oxygenated.to_maze_key(), oxygenated);
-                        oxygen_coordinates.push(get_east_coordinates(&coordinates));
-                    }
-                    _ => (),
-                }
-
-                match maze.get(&get_south_key(&coordinates)).unwrap().kind {
-                    CoordinatesKind::DeadEnd => {
-                        let oxygenated = get_south_coordinates(&coordinates);
-                        maze.insert(oxygenated.to_maze_key(), oxygenated);
-                        oxygen_coordinates.push(get_south_coordinates(&coordinates));
-                    }
-                    _ => (),
-                }
-
-                match maze.get(&get_west_key(&coordinates)).unwrap().kind {
-                    CoordinatesKind::DeadEnd => {
-                        let oxygenated = get_west_coordinates(&coordinates);
-                        maze.insert(oxygenated.to_maze_key(), oxygenated);
-                        oxygen_coordinates.push(get_west_coordinates(&coordinates));
-                    }
-                    _ => (),
-                }
-
-                // print_maze(&maze, &coordinates, &Direction::North);
-            }
-            _ => break,
+    let maze = match env::args().nth(1).as_deref() {
+        Some("solve") => Maze::load(&env::args().nth(2).expect("usage: solve <file>"))?,
+        _ => {
+            let program: Vec<isize> = read_to_string("src/bin/day15-oxygen-system/input.txt")?
+                .trim()
+                .split(',')
+                .map(|line| line.parse::<isize>().unwrap())
+                .collect();
+            explore(&mut IntcodeDroid::new(&program))
         }
-    }
-
-    let mut oxygen_minutes = usize::min_value();
-    for coordinate in maze.values().filter(|c| c.kind == CoordinatesKind::Oxygen) {
-        if coordinate.distance > oxygen_minutes {
-            oxygen_minutes = coordinate.distance;
+    };
+    match env::args().nth(1).as_deref() {
+        Some("map") => println!("{}", maze.render()),
+        Some("save") => maze.save(&env::args().nth(2).expect("usage: save <file>"))?,
+        Some("path") => {
+            let oxygen = maze.oxygen.ok_or_else(no_oxygen)?;
+            let moves = maze.moves(maze.start, oxygen).unwrap_or_default();
+            let moves: Vec<String> = moves.iter().map(|m| format!("{:?}", m)).collect();
+            println!("{}", moves.join(" "));
         }
-    }
-    oxygen_minutes
-}
-
-fn walk_program(
-    program: &Vec<isize>,
-    maze: &mut HashMap<String, Coordinates>,
-) -> Option<Coordinates> {
-    let mut drone_coordinates = Coordinates::new(0, 0, None, None);
-    let mut current_direction = Direction::North;
-    let mut needs_return = false;
-    let mut oxygen_center: Option<Coordinates> = None;
-    maze.insert(drone_coordinates.to_maze_key(), drone_coordinates.clone());
-    let mut computer = IntcodeComputer::new();
-    computer.reset();
-    computer.push_instructions(program.clone());
-
-    loop {
-        match computer.run() {
-            ProgramState::Halted => break,
-            ProgramState::NeedInput => {
-                // print_maze(&maze, &drone_coordinates, &current_direction);
-
-                match current_direction {
-                    Direction::North => computer.push_input(1),
-                    Direction::East => computer.push_input(4),
-                    Direction::South => computer.push_input(2),
-                    Direction::West => computer.push_input(3),
-                };
-            }
-            ProgramState::Output(out) => match out {
-                0 => {
-                    update_maze(
-                        maze,
-                        &drone_coordinates,
-                        &current_direction,
-                        &CoordinatesKind::Wall,
-                    );
-
-                    if !was_on_each_cell(&maze, &drone_coordinates) {
-                        match current_direction {
-                            Direction::North => {
-                                if !has_walked_east(&maze, &drone_coordinates) {
-                                    current_direction = Direction::East;
-                                } else if !has_walked_south(&maze, &drone_coordinates) {
-                                    current_direction = Direction::South;
-                                } else {
-                                    current_direction = Direction::West;
-                                }
-                            }
-                            Direction::East => {
-                                if !has_walked_south(&maze, &drone_coordinates) {
-                                    current_direction = Direction::South;
-                                } else {
-                                    current_direction = Direction::West;
-                                }
-                            }
-                            Direction::South => current_direction = Direction::West,
-                            Direction::West => current_direction = Direction::North,
-                        }
-                        continue;
-                    }
-
-                    if is_north_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::North;
-                        continue;
-                    }
-
-                    if is_east_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::East;
-                        continue;
-                    }
-
-                    if is_south_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::South;
-                        continue;
-                    }
-
-                    if is_west_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::West;
-                        continue;
-                    }
-
-                    break;
-                }
-                1 | 2 => {
-                    if needs_return {
-                        needs_return = false;
-                    } else {
-                        update_maze(
-                            maze,
-                            &drone_coordinates,
-                            &current_direction,
-                            &CoordinatesKind::Free,
-                        );
-                        needs_return = !was_on_each_cell(&maze, &drone_coordinates);
-                        if needs_return {
-                            current_direction = match current_direction {
-                                Direction::North => Direction::South,
-                                Direction::East => Direction::West,
-                                Direction::South => Direction::North,
-                                Direction::West => Direction::East,
-                            };
-                            continue;
-                        }
-                    }
-
-                    // Explore each tile
-                    if !map_has_north(&maze, &drone_coordinates) {
-                        current_direction = Direction::North;
-                        continue;
-                    }
-
-                    if !map_has_east(&maze, &drone_coordinates) {
-                        current_direction = Direction::East;
-                        continue;
-                    }
-
-                    if !map_has_south(&maze, &drone_coordinates) {
-                        current_direction = Direction::South;
-                        continue;
-                    }
-
-                    if !map_has_west(&maze, &drone_coordinates) {
-                        current_direction = Direction::West;
-                        continue;
-                    }
-
-                    update_drone(&mut drone_coordinates, &current_direction, &maze);
-
-                    if out == 2 {
-                        oxygen_center = Some(drone_coordinates.clone());
-                    }
-
-                    // Walk each tile
-                    if !has_walked_north(&maze, &drone_coordinates) {
-                        current_direction = Direction::North;
-                        continue;
-                    }
-
-                    if !has_walked_east(&maze, &drone_coordinates) {
-                        current_direction = Direction::East;
-                        continue;
-                    }
-
-                    if !has_walked_south(&maze, &drone_coordinates) {
-                        current_direction = Direction::South;
-                        continue;
-                    }
-
-                    if !has_walked_west(&maze, &drone_coordinates) {
-                        current_direction = Direction::West;
-                        continue;
-                    }
-
-                    // Dead end
-                    let key = &drone_coordinates.to_maze_key();
-                    let mut cell = maze.get(key).unwrap().clone();
-                    cell.kind = CoordinatesKind::DeadEnd;
-                    maze.insert(key.clone(), cell);
-
-                    if is_north_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::North;
-                        continue;
-                    }
-
-                    if is_east_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::East;
-                        continue;
-                    }
-
-                    if is_south_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::South;
-                        continue;
-                    }
-
-                    if is_west_free(&maze, &drone_coordinates) {
-                        current_direction = Direction::West;
-                        continue;
-                    }
-
-                    break;
-                }
-                n => panic!("Unkonwn output {}", n),
-            },
+        Some("fill") => {
+            let oxygen = maze.oxygen.ok_or_else(no_oxygen)?;
+            let fill = maze.flood_fill(oxygen);
+            println!(
+                "{} minutes, last cells {:?}",
+                fill.minutes(),
+                fill.last_cells()
+            );
         }
-    }
-
-    oxygen_center
-}
-
-fn update_maze(
-    maze: &mut HashMap<String, Coordinates>,
-    drone_coordinates: &Coordinates,
-    current_direction: &Direction,
-    kind: &CoordinatesKind,
-) -> () {
-    let coordinates = match current_direction {
-        Direction::North => Coordinates::new(
-            drone_coordinates.x,
-            drone_coordinates.y + 1,
-            Some(drone_coordinates.distance + 1),
-            Some(kind.clone()),
-        ),
-        Direction::East => Coordinates::new(
-            drone_coordinates.x + 1,
-            drone_coordinates.y,
-            Some(drone_coordinates.distance + 1),
-            Some(kind.clone()),
-        ),
-        Direction::South => Coordinates::new(
-            drone_coordinates.x,
-            drone_coordinates.y - 1,
-            Some(drone_coordinates.distance + 1),
-            Some(kind.clone()),
-        ),
-        Direction::West => Coordinates::new(
-            drone_coordinates.x - 1,
-            drone_coordinates.y,
-            Some(drone_coordinates.distance + 1),
-            Some(kind.clone()),
-        ),
-    };
-
-    let key = coordinates.to_maze_key();
-    if !maze.contains_key(&key) {
-        maze.insert(coordinates.to_maze_key(), coordinates.clone());
-    } else {
-        if maze.get(&key).unwrap().distance > coordinates.distance {
-            maze.insert(coordinates.to_maze_key(), coordinates.clone());
+        _ => {
+            let oxygen = maze.oxygen.ok_or_else(no_oxygen)?;
+            let path = maze.shortest_path(maze.start, oxygen).ok_or_else(no_path)?;
+            println!("{}", path.len() - 1);
+            println!("{}", spread_oxygen(&maze, oxygen));
         }
     }
-}
 
-fn update_drone(
-    coordinate: &mut Coordinates,
-    current_direction: &Direction,
-    maze: &HashMap<String, Coordinates>,
-) {
-    match current_direction {
-        Direction::North => coordinate.y += 1,
-        Direction::East => coordinate.x += 1,
-        Direction::South => coordinate.y -= 1,
-        Direction::West => coordinate.x -= 1,
-    };
-    let distance = match maze.get(&coordinate.to_maze_key()) {
-        Some(c) => c.distance,
-        _ => coordinate.distance + 1,
-    };
-    coordinate.distance = distance;
-}
-
-fn was_on_each_cell(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    map_has_north(maze, drone_coordinates)
-        && map_has_east(maze, drone_coordinates)
-        && map_has_south(maze, drone_coordinates)
-        && map_has_west(maze, drone_coordinates)
-}
-
-fn map_has_north(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    map_has_coordinates(maze, &get_north_key(drone_coordinates))
-}
-
-fn map_has_east(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    map_has_coordinates(maze, &get_east_key(drone_coordinates))
+    Ok(())
 }
 
-fn map_has_south(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    map_has_coordinates(maze, &get_south_key(drone_coordinates))
+fn no_oxygen() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "No oxygen system found")
 }
 
-fn map_has_west(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    map_has_coordinates(maze, &get_west_key(drone_coordinates))
+fn no_path() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "No path to the oxygen system")
 }
 
-fn map_has_coordinates(maze: &HashMap<String, Coordinates>, key: &String) -> bool {
-    maze.contains_key(key)
+fn spread_oxygen(maze: &Maze, oxygen: (isize, isize)) -> usize {
+    maze.flood_fill(oxygen).minutes()
 }
 
-fn has_walked_north(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    has_walked_coordinate(
-        maze,
-        &get_north_key(drone_coordinates),
-        &drone_coordinates.distance,
-    )
+#[derive(Debug, Clone, PartialEq)]
+struct FloodFill {
+    fronts: Vec<Vec<(isize, isize)>>,
 }
 
-fn has_walked_east(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    has_walked_coordinate(
-        maze,
-        &get_east_key(drone_coordinates),
-        &drone_coordinates.distance,
-    )
-}
+impl FloodFill {
+    fn minutes(&self) -> usize {
+        self.fronts.len().saturating_sub(1)
+    }
 
-fn has_walked_south(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    has_walked_coordinate(
-        maze,
-        &get_south_key(drone_coordinates),
-        &drone_coordinates.distance,
-    )
+    fn last_cells(&self) -> &[(isize, isize)] {
+        self.fronts.last().map_or(&[], |front| front.as_slice())
+    }
 }
 
-fn has_walked_west(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    has_walked_coordinate(
-        maze,
-        &get_west_key(drone_coordinates),
-        &drone_coordinates.distance,
-    )
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    North,
+    South,
+    West,
+    East,
 }
 
-fn has_walked_coordinate(
-    maze: &HashMap<String, Coordinates>,
-    key: &String,
-    distance: &usize,
-) -> bool {
-    if !maze.contains_key(key) {
-        return false;
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    fn command(self) -> isize {
+        match self {
+            Direction::North => 1,
+            Direction::South => 2,
+            Direction::West => 3,
+            Direction::East => 4,
+        }
     }
 
-    let coordinate = maze.get(key).unwrap();
-    match coordinate.kind {
-        CoordinatesKind::Wall | CoordinatesKind::DeadEnd => true,
-        CoordinatesKind::Free | CoordinatesKind::Oxygen => &coordinate.distance <= distance,
+    fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
     }
-}
 
-fn is_north_free(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    is_coordinate_free(maze, &get_north_key(drone_coordinates))
+    fn step(self, (x, y): (isize, isize)) -> (isize, isize) {
+        match self {
+            Direction::North => (x, y + 1),
+            Direction::South => (x, y - 1),
+            Direction::West => (x - 1, y),
+            Direction::East => (x + 1, y),
+        }
+    }
 }
 
-fn is_east_free(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    is_coordinate_free(maze, &get_east_key(drone_coordinates))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Wall,
+    Open,
+    Oxygen,
 }
 
-fn is_south_free(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    is_coordinate_free(maze, &get_south_key(drone_coordinates))
+impl Cell {
+    fn from_status(status: isize) -> Self {
+        match status {
+            0 => Cell::Wall,
+            1 => Cell::Open,
+            2 => Cell::Oxygen,
+            n => panic!("Unknown status {}", n),
+        }
+    }
 }
 
-fn is_west_free(maze: &HashMap<String, Coordinates>, drone_coordinates: &Coordinates) -> bool {
-    is_coordinate_free(maze, &get_west_key(drone_coordinates))
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Maze {
+    cells: HashMap<(isize, isize), Cell>,
+    start: (isize, isize),
+    oxygen: Option<(isize, isize)>,
 }
 
-fn is_coordinate_free(maze: &HashMap<String, Coordinates>, key: &String) -> bool {
-    if !maze.contains_key(key) {
-        return true;
+impl Maze {
+    fn cell(&self, coordinates: (isize, isize)) -> Option<Cell> {
+        self.cells.get(&coordinates).copied()
     }
 
-    let coordinate = maze.get(key).unwrap();
-    match coordinate.kind {
-        CoordinatesKind::Wall | CoordinatesKind::DeadEnd => false,
-        CoordinatesKind::Free | CoordinatesKind::Oxygen => true,
+    fn is_open(&self, coordinates: (isize, isize)) -> bool {
+        matches!(
+            self.cell(coordinates),
+            Some(Cell::Open) | Some(Cell::Oxygen)
+        )
     }
-}
-
-fn get_north_key(coordinates: &Coordinates) -> String {
-    get_north_coordinates(coordinates).to_maze_key()
-}
 
-fn get_east_key(coordinates: &Coordinates) -> String {
-    get_east_coordinates(coordinates).to_maze_key()
-}
-
-fn get_south_key(coordinates: &Coordinates) -> String {
-    get_south_coordinates(coordinates).to_maze_key()
-}
-
-fn get_west_key(coordinates: &Coordinates) -> String {
-    get_west_coordinates(coordinates).to_maze_key()
-}
-
-fn get_north_coordinates(coordinates: &Coordinates) -> Coordinates {
-    Coordinates::new(
-        coordinates.x,
-        coordinates.y + 1,
-        Some(coordinates.distance + 1),
-        Some(coordinates.kind.clone()),
-    )
-}
+    fn insert(&mut self, coordinates: (isize, isize), cell: Cell) {
+        if cell == Cell::Oxygen {
+            self.oxygen = Some(coordinates);
+        }
+        self.cells.insert(coordinates, cell);
+    }
 
-fn get_east_coordinates(coordinates: &Coordinates) -> Coordinates {
-    Coordinates::new(
-        coordinates.x + 1,
-        coordinates.y,
-        Some(coordinates.distance + 1),
-        Some(coordinates.kind.clone()),
-    )
-}
+    fn neighbours(&self, coordinates: (isize, isize)) -> Vec<(isize, isize)> {
+        Direction::ALL
+            .iter()
+            .map(|direction| direction.step(coordinates))
+            .filter(|&next| self.is_open(next))
+            .collect()
+    }
 
-fn get_south_coordinates(coordinates: &Coordinates) -> Coordinates {
-    Coordinates::new(
-        coordinates.x,
-        coordinates.y - 1,
-        Some(coordinates.distance + 1),
-        Some(coordinates.kind.clone()),
-    )
-}
+    fn shortest_path(
+        &self,
+        from: (isize, isize),
+        to: (isize, isize),
+    ) -> Option<Vec<(isize, isize)>> {
+        if !self.is_open(from) || !self.is_open(to) {
+            return None;
+        }
+        bfs(
+            &from,
+            |&coordinates| self.neighbours(coordinates),
+            |&c| c == to,
+        )
+    }
 
-fn get_west_coordinates(coordinates: &Coordinates) -> Coordinates {
-    Coordinates::new(
-        coordinates.x - 1,
-        coordinates.y,
-        Some(coordinates.distance + 1),
-        Some(coordinates.kind.clone()),
-    )
-}
+    fn moves(&self, from: (isize, isize), to: (isize, isize)) -> Option<Vec<Direction>> {
+        let path = self.shortest_path(from, to)?;
+        Some(
+            path.windows(2)
+                .map(|pair| {
+                    *Direction::ALL
+                        .iter()
+                        .find(|direction| direction.step(pair[0]) == pair[1])
+                        .unwrap()
+                })
+                .collect(),
+        )
+    }
 
-fn print_maze(
-    maze: &HashMap<String, Coordinates>,
-    drone_coordinates: &Coordinates,
-    current_direction: &Direction,
-) -> () {
-    let mut min_x = isize::max_value();
-    let mut max_x = isize::min_value();
-    let mut min_y = isize::max_value();
-    let mut max_y = isize::min_value();
-
-    for coordinate in maze.values() {
-        if coordinate.x > max_x {
-            max_x = coordinate.x
+    fn distances(&self, from: (isize, isize)) -> HashMap<(isize, isize), usize> {
+        if !self.is_open(from) {
+            return HashMap::new();
         }
+        let mut distances: HashMap<(isize, isize), usize> = dijkstra_all(&from, |&coordinates| {
+            self.neighbours(coordinates)
+                .into_iter()
+                .map(|next| (next, 1))
+        })
+        .into_iter()
+        .map(|(coordinates, (_, distance))| (coordinates, distance))
+        .collect();
+        distances.insert(from, 0);
+        distances
+    }
 
-        if coordinate.x < min_x {
-            min_x = coordinate.x
+    fn flood_fill(&self, source: (isize, isize)) -> FloodFill {
+        let distances = self.distances(source);
+        let minutes = distances.values().copied().max();
+        let mut fronts = vec![vec![]; minutes.map_or(0, |minutes| minutes + 1)];
+        for (&coordinates, &distance) in &distances {
+            fronts[distance].push(coordinates);
         }
-
-        if coordinate.y > max_y {
-            max_y = coordinate.y
+        for front in fronts.iter_mut() {
+            front.sort_unstable();
         }
+        FloodFill { fronts }
+    }
 
-        if coordinate.y < min_y {
-            min_y = coordinate.y
-        }
+    fn render(&self) -> String {
+        let min_x = self.cells.keys().map(|c| c.0).min().unwrap_or(0);
+        let max_x = self.cells.keys().map(|c| c.0).max().unwrap_or(0);
+        let min_y = self.cells.keys().map(|c| c.1).min().unwrap_or(0);
+        let max_y = self.cells.keys().map(|c| c.1).max().unwrap_or(0);
+
+        (min_y..=max_y)
+            .rev()
+            .map(|y| {
+                (min_x..=max_x)
+                    .map(|x| match self.cell((x, y)) {
+                        Some(Cell::Oxygen) if (x, y) == self.start => '@',
+                        _ if (x, y) == self.start => 'D',
+                        Some(Cell::Wall) => '#',
+                        Some(Cell::Open) => '.',
+                        Some(Cell::Oxygen) => 'O',
+                        None => ' ',
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_owned()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
     }
 
-    let mut graphic_maze: Vec<Vec<String>> = vec![];
+    fn load(path: &str) -> io::Result<Self> {
+        read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
 
-    for y in min_y..max_y + 1 {
-        let mut line: Vec<String> = vec![];
-        for x in min_x..max_x + 1 {
-            if x == drone_coordinates.x && y == drone_coordinates.y {
-                match current_direction {
-                    Direction::North => line.push("^".to_owned()),
-                    Direction::East => line.push(">".to_owned()),
-                    Direction::South => line.push("v".to_owned()),
-                    Direction::West => line.push("<".to_owned()),
-                }
-                continue;
-            }
+    fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.render() + "\n")
+    }
+}
+
+impl FromStr for Maze {
+    type Err = String;
 
-            let key = Coordinates::new(x, y, None, None).to_maze_key();
-            if maze.contains_key(&key) {
-                let coords = maze.get(&key).unwrap();
-                match coords.kind {
-                    CoordinatesKind::Wall => line.push("#".to_owned()),
-                    CoordinatesKind::DeadEnd => line.push("X".to_owned()),
-                    CoordinatesKind::Oxygen => line.push("O".to_owned()),
-                    CoordinatesKind::Free => {
-                        line.push((coords.distance % 10).to_string().to_owned())
+    fn from_str(map: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = map.lines().collect();
+        let starts: Vec<(isize, isize)> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == 'D' || c == '@')
+                    .map(move |(column, _)| (column as isize, row as isize))
+            })
+            .collect();
+        if starts.len() > 1 {
+            return Err(format!("Multiple starts in map at {:?}", starts));
+        }
+        let origin = starts.first().copied().ok_or("No start in map")?;
+
+        let mut maze = Maze::default();
+        for (row, line) in lines.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let coordinates = (column as isize - origin.0, origin.1 - row as isize);
+                let cell = match c {
+                    '#' => Cell::Wall,
+                    '.' | 'D' => Cell::Open,
+                    'O' | '@' if maze.oxygen.is_some() => {
+                        return Err("Multiple oxygen systems in map".to_owned())
                     }
-                }
-            } else {
-                line.push(" ".to_owned());
+                    'O' | '@' => Cell::Oxygen,
+                    ' ' => continue,
+                    c => return Err(format!("Invalid map character {} at line {}", c, row + 1)),
+                };
+                maze.insert(coordinates, cell);
             }
         }
-        graphic_maze.push(line)
+        Ok(maze)
     }
+}
 
-    graphic_maze.reverse();
-
-    println!("");
-    println!(
-        "{}",
-        graphic_maze
-            .iter()
-            .map(|l| l.join(""))
-            .collect::<Vec<String>>()
-            .join("\n")
-    );
-    println!("");
+trait Droid {
+    fn try_move(&mut self, direction: Direction) -> Cell;
 }
 
-#[derive(Debug)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
+struct IntcodeDroid {
+    computer: IntcodeComputer,
 }
 
-#[derive(Clone, Debug, PartialEq)]
-enum CoordinatesKind {
-    Wall,
-    DeadEnd,
-    Free,
-    Oxygen,
+impl IntcodeDroid {
+    fn new(program: &[isize]) -> Self {
+        let mut computer = IntcodeComputer::new();
+        computer.push_instructions(program.to_vec());
+        IntcodeDroid { computer }
+    }
 }
 
-#[derive(Clone, Debug)]
-struct Coordinates {
-    x: isize,
-    y: isize,
-    distance: usize,
-    kind: CoordinatesKind,
+impl Droid for IntcodeDroid {
+    fn try_move(&mut self, direction: Direction) -> Cell {
+        self.computer.push_input(direction.command());
+        match self.computer.run() {
+            ProgramState::Output(status) => Cell::from_status(status),
+            ProgramState::NeedInput => panic!("Droid asked for input before reporting"),
+            ProgramState::Halted => panic!("Droid halted while moving"),
+        }
+    }
 }
 
-impl Coordinates {
-    fn new(x: isize, y: isize, distance: Option<usize>, kind: Option<CoordinatesKind>) -> Self {
-        Coordinates {
-            x: x,
-            y: y,
-            distance: match distance {
-                Some(d) => d,
-                _ => 0,
-            },
-            kind: match kind {
-                Some(k) => k,
-                _ => CoordinatesKind::Free,
+fn explore<D: Droid>(droid: &mut D) -> Maze {
+    let mut maze = Maze::default();
+    maze.insert((0, 0), Cell::Open);
+    let mut position = (0, 0);
+    let mut path: Vec<Direction> = vec![];
+
+    loop {
+        let unexplored = Direction::ALL
+            .iter()
+            .copied()
+            .find(|direction| maze.cell(direction.step(position)).is_none());
+
+        match unexplored {
+            Some(direction) => {
+                let cell = droid.try_move(direction);
+                maze.insert(direction.step(position), cell);
+                if cell != Cell::Wall {
+                    position = direction.step(position);
+                    path.push(direction);
+                }
+            }
+            None => match path.pop() {
+                Some(direction) => {
+                    let back = direction.opposite();
+                    if droid.try_move(back) == Cell::Wall {
+                        panic!("Droid could not backtrack from {:?}", position);
+                    }
+                    position = back.step(position);
+                }
+                None => break,
             },
         }
     }
 
-    fn to_maze_key(&self) -> String {
-        self.x.to_string() + "," + &self.y.to_string()
-    }
+    maze
 }
 
 enum ProgramState {
@@ -634,16 +390,9 @@ impl IntcodeComputer {
         }
     }
 
-    fn reset(&mut self) {
-        self.instructions.clear();
-        self.pointer = 0;
-        self.relative_base = 0;
-        self.inputs.clear();
-    }
-
     fn push_instructions(&mut self, program: Vec<isize>) {
-        for ind in 0..program.len() {
-            self.instructions.insert(ind as isize, program[ind]);
+        for (ind, value) in program.into_iter().enumerate() {
+            self.instructions.insert(ind as isize, value);
         }
     }
 
@@ -748,13 +497,13 @@ impl IntcodeComputer {
 
     fn get_paramter(&self, mode: &isize, ind: &isize) -> isize {
         match mode {
-            0 => match self.instructions.get(&ind) {
-                Some(x) => x.clone(),
+            0 => match self.instructions.get(ind) {
+                Some(x) => *x,
                 _ => 0,
             },
-            1 => ind.clone(),
-            2 => match self.instructions.get(&ind) {
-                Some(x) => (self.relative_base + x.clone()),
+            1 => *ind,
+            2 => match self.instructions.get(ind) {
+                Some(x) => self.relative_base + *x,
                 _ => self.relative_base,
             },
             n => panic!("Unknown mode {}", n),
@@ -763,8 +512,246 @@ impl IntcodeComputer {
 
     fn get_value(&self, param: &isize) -> isize {
         match self.instructions.get(param) {
-            Some(x) => x.clone(),
+            Some(x) => *x,
             _ => 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::explore;
+    use super::spread_oxygen;
+    use super::Cell;
+    use super::Direction;
+    use super::Droid;
+    use super::FloodFill;
+    use super::Maze;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn fixture(map: &str) -> Maze {
+        map.trim_start_matches('\n').parse().unwrap()
+    }
+
+    struct FakeDroid {
+        maze: Maze,
+        position: (isize, isize),
+    }
+
+    impl FakeDroid {
+        fn new(map: &str) -> Self {
+            FakeDroid {
+                maze: fixture(map),
+                position: (0, 0),
+            }
+        }
+    }
+
+    impl Droid for FakeDroid {
+        fn try_move(&mut self, direction: Direction) -> Cell {
+            let next = direction.step(self.position);
+            let cell = self.maze.cell(next).unwrap_or(Cell::Wall);
+            if cell != Cell::Wall {
+                self.position = next;
+            }
+            cell
+        }
+    }
+
+    #[test]
+    fn it_should_explore_every_reachable_cell() {
+        let mut droid = FakeDroid::new(
+            r"
+#########
+#D..#...#
+#.#.#.#.#
+#.#...#O#
+#########",
+        );
+        let maze = explore(&mut droid);
+
+        assert_eq!(maze.oxygen, Some((6, -2)));
+        assert_eq!(
+            maze.cells
+                .values()
+                .filter(|&&cell| cell != Cell::Wall)
+                .count(),
+            15
+        );
+        assert_eq!(droid.position, (0, 0));
+        assert_eq!(
+            maze.render(),
+            [
+                " ### ###",
+                "#D..#...#",
+                "#.#.#.#.#",
+                "#.#...#O#",
+                " # ### #",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_should_explore_loops_and_open_rooms() {
+        let mut droid = FakeDroid::new(
+            r"
+#######
+#.....#
+#.#.#.#
+#..D..#
+#.#.#O#
+#######",
+        );
+        let maze = explore(&mut droid);
+
+        assert_eq!(maze.distances(maze.start)[&maze.oxygen.unwrap()], 3);
+        assert_eq!(
+            maze.moves(maze.start, maze.oxygen.unwrap()),
+            Some(vec![Direction::East, Direction::East, Direction::South])
+        );
+        assert_eq!(
+            maze.shortest_path((-2, 0), (2, 0)).map(|path| path.len()),
+            Some(5)
+        );
+        assert_eq!(maze.shortest_path((-2, 0), (-1, -1)), None);
+        assert_eq!(
+            maze.cells
+                .values()
+                .filter(|&&cell| cell != Cell::Wall)
+                .count(),
+            16
+        );
+    }
+
+    #[test]
+    fn it_should_spread_oxygen() {
+        let mut maze = Maze::default();
+        let cells = [(0, 0), (1, 0), (2, 0), (1, 1), (1, 2)];
+        for &coordinates in cells.iter() {
+            maze.insert(coordinates, Cell::Open);
+        }
+        maze.insert((1, 2), Cell::Oxygen);
+
+        assert_eq!(spread_oxygen(&maze, (1, 2)), 3);
+        assert_eq!(spread_oxygen(&maze, (1, 0)), 2);
+    }
+
+    #[test]
+    fn it_should_flood_fill_in_time_steps() {
+        let mut maze = Maze::default();
+        let cells = [(0, 0), (1, 0), (2, 0), (1, 1), (1, 2), (3, 0)];
+        for &coordinates in cells.iter() {
+            maze.insert(coordinates, Cell::Open);
+        }
+        maze.insert((0, 1), Cell::Wall);
+
+        assert_eq!(
+            maze.flood_fill((1, 1)),
+            FloodFill {
+                fronts: vec![
+                    vec![(1, 1)],
+                    vec![(1, 0), (1, 2)],
+                    vec![(0, 0), (2, 0)],
+                    vec![(3, 0)],
+                ]
+            }
+        );
+        assert_eq!(maze.flood_fill((1, 1)).minutes(), 3);
+        assert_eq!(maze.flood_fill((0, 1)).minutes(), 0);
+        assert!(maze.flood_fill((0, 1)).last_cells().is_empty());
+    }
+
+    #[test]
+    fn it_should_spread_oxygen_in_hand_drawn_mazes() {
+        let maze = fixture(
+            r"
+ ##
+#D.##
+#.#..#
+#.O.#
+ ###",
+        );
+
+        assert_eq!(maze.oxygen, Some((1, -2)));
+        assert_eq!(spread_oxygen(&maze, maze.oxygen.unwrap()), 4);
+
+        let maze = fixture(
+            r"
+#######
+#D....#
+###.###
+#O..#.#
+#######",
+        );
+
+        assert_eq!(maze.start, (0, 0));
+        assert_eq!(maze.oxygen, Some((0, -2)));
+        assert_eq!(
+            maze.moves(maze.start, maze.oxygen.unwrap())
+                .map(|m| m.len()),
+            Some(6)
+        );
+        assert_eq!(spread_oxygen(&maze, maze.oxygen.unwrap()), 6);
+    }
+
+    #[test]
+    fn it_should_reject_invalid_maps() {
+        assert!("#D#\n#D#".parse::<Maze>().is_err());
+        assert!("#O#\n#O#".parse::<Maze>().is_err());
+        assert!("#x#".parse::<Maze>().is_err());
+        assert_eq!(
+            "#####\n#.O.#\n#####".parse::<Maze>(),
+            Err("No start in map".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_should_keep_an_oxygen_system_on_the_start_cell() {
+        let maze = fixture(
+            r"
+####
+#@.#
+####",
+        );
+
+        assert_eq!(maze.start, (0, 0));
+        assert_eq!(maze.oxygen, Some((0, 0)));
+        assert_eq!(maze.render(), "####\n#@.#\n####");
+        assert_eq!(maze.render().parse::<Maze>(), Ok(maze));
+    }
+
+    #[test]
+    fn it_should_save_and_load_explored_mazes() {
+        let maze = explore(&mut FakeDroid::new(
+            r"
+#######
+#.....#
+#.#.#.#
+#..D..#
+#.#.#O#
+#######",
+        ));
+        let path = env::temp_dir().join(format!("day15-oxygen-system-maze-{}.txt", process::id()));
+        let path = path.to_str().unwrap();
+        maze.save(path).unwrap();
+        let loaded = Maze::load(path);
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.unwrap(), maze);
+    }
+
+    #[test]
+    fn it_should_solve_the_saved_puzzle_maze() {
+        let maze = Maze::load("src/bin/day15-oxygen-system/maze.txt").unwrap();
+        let oxygen = maze.oxygen.unwrap();
+
+        assert_eq!(
+            maze.shortest_path(maze.start, oxygen).unwrap().len() - 1,
+            230
+        );
+        assert_eq!(spread_oxygen(&maze, oxygen), 288);
+    }
+}